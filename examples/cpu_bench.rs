@@ -0,0 +1,171 @@
+//! CPU interpreter benchmark
+//!
+//! Runs a small NSF-like play routine (table reads through a pointer,
+//! arithmetic and APU register writes) and prints the instructions per second
+//! with the bus statically dispatched and through `dyn BusMut`, on a
+//! [`MemoryMap`] and on a flat 64 KiB array. The flat array behind a
+//! [`SharedBus`] over `dyn Bus` is the baseline: every access goes through
+//! the legacy shared bus, as it did before the cpu took a [`BusMut`].
+//!
+//! Run with `cargo run --release --example cpu_bench [instructions]`.
+
+use std::cell::RefCell;
+use std::time::Instant;
+
+use redNSF::op65::context::{AddressError, Bus, BusMut, SharedBus};
+use redNSF::op65::cpu::Cpu;
+use redNSF::op65::memmap::MemoryMap;
+
+/// play loop at $8000, play routine at $8010
+const PROGRAM: [(u16, &[u8]); 2] = [
+    (
+        0x8000,
+        &[
+            0x20, 0x10, 0x80, // JSR $8010
+            0x4C, 0x00, 0x80, // JMP $8000
+        ],
+    ),
+    (
+        0x8010,
+        &[
+            0xA2, 0x04, // LDX #$04
+            0xB4, 0x10, // LDY $10,X
+            0xB1, 0x20, // LDA ($20),Y
+            0x18, // CLC
+            0x75, 0x30, // ADC $30,X
+            0x95, 0x30, // STA $30,X
+            0x9D, 0x00, 0x40, // STA $4000,X
+            0xF6, 0x10, // INC $10,X
+            0xB5, 0x10, // LDA $10,X
+            0x29, 0x1F, // AND #$1F
+            0x95, 0x10, // STA $10,X
+            0xCA, // DEX
+            0x10, 0xE9, // BPL $8012
+            0xA5, 0x40, // LDA $40
+            0x0A, // ASL A
+            0x26, 0x41, // ROL $41
+            0x85, 0x40, // STA $40
+            0x60, // RTS
+        ],
+    ),
+];
+
+/// flat 64 KiB of RAM, the cheapest possible bus
+struct Flat(Vec<u8>);
+
+impl BusMut for Flat {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        Ok(self.0[addr as usize])
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        self.0[addr as usize] = val;
+        Ok(())
+    }
+}
+
+/// flat 64 KiB of RAM behind the legacy shared bus
+struct Shared(RefCell<Vec<u8>>);
+
+impl Bus for Shared {
+    fn get(&self, addr: u16) -> Result<u8, AddressError> {
+        Ok(self.0.borrow()[addr as usize])
+    }
+
+    fn set(&self, addr: u16, val: u8) -> Result<(), AddressError> {
+        self.0.borrow_mut()[addr as usize] = val;
+        Ok(())
+    }
+
+    fn get_pointer(&self, addr: u16) -> Result<u16, AddressError> {
+        let lo = self.get(addr)?;
+        let hi = self.get(addr.wrapping_add(1))?;
+        Ok(u16::from_le_bytes([lo, hi]))
+    }
+
+    /// the RAM cannot be borrowed out of its cell
+    fn get_window(&self, addr: u16, _: u16) -> Result<&[u8], AddressError> {
+        Err(AddressError::Unavailable(addr))
+    }
+
+    fn set_window(&self, addr: u16, val: &[u8]) -> Result<(), AddressError> {
+        let start = addr as usize;
+        self.0.borrow_mut()[start..start + val.len()].copy_from_slice(val);
+        Ok(())
+    }
+}
+
+/// 32 KiB ROM at $8000 with the program, a table at $8100 and the vectors
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    for (addr, code) in PROGRAM {
+        let at = (addr - 0x8000) as usize;
+        rom[at..at + code.len()].copy_from_slice(code);
+    }
+    for (i, b) in rom[0x0100..0x0120].iter_mut().enumerate() {
+        *b = (i as u8).wrapping_mul(37);
+    }
+    rom[0x7FFC..0x7FFE].copy_from_slice(&[0x00, 0x80]);
+    rom
+}
+
+/// reset a cpu on the bus, with the table pointer at $20 set up
+fn boot<B: BusMut + ?Sized>(bus: &mut B) -> Cpu {
+    let mut cpu = Cpu::new();
+    bus.set_window(0x0020, &[0x00, 0x81]).unwrap();
+    cpu.reset(bus).unwrap();
+    cpu
+}
+
+fn memory_map() -> MemoryMap {
+    MemoryMap::new()
+        .with_ram(0x0000, 0x1FFF, 0x800)
+        .with_write_only(0x4000, 0x4017, |_, _| ())
+        .with_rom(0x8000, 0xFFFF, rom())
+}
+
+fn flat() -> Flat {
+    let mut ram = vec![0; 0x8000];
+    ram.extend(rom());
+    Flat(ram)
+}
+
+fn shared() -> Shared {
+    Shared(RefCell::new(flat().0))
+}
+
+/// run the instructions and return the instructions per second
+fn run<B: BusMut + ?Sized>(bus: &mut B, count: u64) -> f64 {
+    let mut cpu = boot(bus);
+    let start = Instant::now();
+    for _ in 0..count {
+        cpu.step(bus).unwrap();
+    }
+    count as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let count = std::env::args()
+        .nth(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(20_000_000);
+
+    let shared = shared();
+    let results = [
+        (
+            "shared, dyn Bus",
+            run(&mut SharedBus(&shared as &dyn Bus), count),
+        ),
+        ("memory map, generic", run(&mut memory_map(), count)),
+        (
+            "memory map, dyn",
+            run(&mut memory_map() as &mut dyn BusMut, count),
+        ),
+        ("flat, generic", run(&mut flat(), count)),
+        ("flat, dyn", run(&mut flat() as &mut dyn BusMut, count)),
+    ];
+    println!("{} instructions", count);
+    for (name, ips) in results {
+        println!("{:<20} {:>8.2} M instructions/s", name, ips / 1e6);
+    }
+}
@@ -1 +1,4 @@
+#![allow(non_snake_case)]
+
+pub mod nsf;
 pub mod op65;
@@ -0,0 +1,243 @@
+//! NSF bankswitching
+//!
+//! A bankswitched NSF is cut into 4 KiB banks, the first one padded at the
+//! front by the low 12 bits of the load address. The registers at
+//! $5FF8-$5FFF select the bank of each 4 KiB page of $8000-$FFFF and start
+//! out with the banks of the header. An FDS tune runs from RAM at
+//! $6000-$FFFF instead: $5FF6 and $5FF7 also switch the pages of
+//! $6000-$7FFF, starting out with the header banks of $E000 and $F000, and a
+//! switch copies the bank into the RAM page. See
+//! <https://www.nesdev.org/wiki/NSF#Bankswitching>.
+//!
+//! [`Bankswitch`] lays out the program data of an [`Nsf`] either way, or at
+//! its load address when the tune is not bankswitched, and serves it as a
+//! [`BusMut`].
+
+use crate::nsf::header::Nsf;
+use crate::op65::context::{AddressError, BusMut};
+
+/// size of a bank and of the page it is mapped to
+pub const BANK_SIZE: usize = 0x1000;
+/// first bank register, of the $6000 page on FDS
+pub const FDS_REGISTERS: u16 = 0x5FF6;
+/// bank register of the $8000 page
+pub const REGISTERS: u16 = 0x5FF8;
+/// first address of the pages
+const BASE: u16 = 0x6000;
+/// number of pages of $6000-$FFFF
+const PAGES: usize = 10;
+
+/// Bankswitch serves the program data of an NSF at $6000-$FFFF and its bank
+/// registers
+pub struct Bankswitch {
+    /// program data padded to whole banks
+    rom: Vec<u8>,
+    /// offset in `rom` of the bank of each page of $6000-$FFFF
+    pages: [usize; PAGES],
+    /// bank of each page after reset
+    init: [u8; PAGES],
+    /// the bank registers respond
+    switched: bool,
+    /// FDS RAM at $6000-$FFFF, $E000-$FFFF is not writable
+    ram: Option<Vec<u8>>,
+}
+
+impl Bankswitch {
+    /// lay out the program data of an NSF with its initial banks
+    pub fn new(nsf: &Nsf) -> Bankswitch {
+        let header = &nsf.header;
+        let fds = header.chips.fds();
+        let switched = header.bankswitched();
+        // first page the tune can occupy
+        let first = if fds { 0 } else { 2 };
+        let (pad, init) = match switched {
+            true => {
+                let mut init = [0; PAGES];
+                init[2..].copy_from_slice(&header.banks);
+                init[0] = header.banks[6];
+                init[1] = header.banks[7];
+                (header.load as usize % BANK_SIZE, init)
+            }
+            // the banks are the pages, in order
+            false => {
+                let mut init = [0; PAGES];
+                for (bank, page) in init[first..].iter_mut().zip(0..) {
+                    *bank = page;
+                }
+                let base = BASE + (first * BANK_SIZE) as u16;
+                (header.load.saturating_sub(base) as usize, init)
+            }
+        };
+        let mut rom = vec![0; pad];
+        rom.extend(&nsf.data);
+        let len = match switched {
+            true => rom.len().div_ceil(BANK_SIZE).max(1) * BANK_SIZE,
+            false => (PAGES - first) * BANK_SIZE,
+        };
+        rom.resize(len, 0);
+        let mut bankswitch = Bankswitch {
+            rom,
+            pages: [0; PAGES],
+            init,
+            switched,
+            ram: fds.then(|| vec![0; PAGES * BANK_SIZE]),
+        };
+        bankswitch.reset();
+        bankswitch
+    }
+
+    /// map the initial banks again. FDS RAM is reloaded.
+    pub fn reset(&mut self) {
+        let first = if self.ram.is_some() { 0 } else { 2 };
+        for page in first..PAGES {
+            self.switch(page, self.init[page]);
+        }
+    }
+
+    /// check whether the bank registers respond
+    pub fn switched(&self) -> bool {
+        self.switched
+    }
+
+    /// number of banks of the program data
+    pub fn banks(&self) -> usize {
+        self.rom.len() / BANK_SIZE
+    }
+
+    /// map a bank to a page, out of range banks wrap around
+    fn switch(&mut self, page: usize, bank: u8) {
+        let offset = bank as usize % self.banks() * BANK_SIZE;
+        if let Some(ram) = &mut self.ram {
+            ram[page * BANK_SIZE..(page + 1) * BANK_SIZE]
+                .copy_from_slice(&self.rom[offset..offset + BANK_SIZE]);
+        }
+        self.pages[page] = offset;
+    }
+}
+
+// BusMut for Bankswitch
+impl BusMut for Bankswitch {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        let at = addr.wrapping_sub(BASE) as usize;
+        match (&self.ram, addr) {
+            (Some(ram), 0x6000..=0xFFFF) => Ok(ram[at]),
+            (None, 0x8000..=0xFFFF) => {
+                Ok(self.rom[self.pages[at / BANK_SIZE] + at % BANK_SIZE])
+            }
+            (Some(_), FDS_REGISTERS..=0x5FFF) | (None, REGISTERS..=0x5FFF) => {
+                Err(AddressError::WriteOnly(addr))
+            }
+            _ => Err(AddressError::Unavailable(addr)),
+        }
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        let first = match self.ram {
+            Some(_) => FDS_REGISTERS,
+            None => REGISTERS,
+        };
+        if (first..BASE).contains(&addr) {
+            if self.switched {
+                self.switch((addr - FDS_REGISTERS) as usize, val);
+            }
+            return Ok(());
+        }
+        match (&mut self.ram, addr) {
+            (Some(ram), 0x6000..=0xDFFF) => {
+                ram[(addr - BASE) as usize] = val;
+                Ok(())
+            }
+            (Some(_), 0xE000..=0xFFFF) | (None, 0x8000..=0xFFFF) => {
+                Err(AddressError::ReadOnly(addr))
+            }
+            _ => Err(AddressError::Unavailable(addr)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nsf::header::TestNsf;
+
+    /// one-song NSF, byte `i` of the program data is `i / $100`
+    fn nsf(load: u16, banks: [u8; 8], chips: u8, len: usize) -> Nsf {
+        let data: Vec<u8> = (0..len).map(|i| (i / 0x100) as u8).collect();
+        TestNsf::new()
+            .with_addresses(load, load, load)
+            .with_banks(banks)
+            .with_chips(chips)
+            .with_data(&data)
+            .nsf()
+    }
+
+    #[test]
+    fn padding() {
+        // the first bank starts $123 bytes before the load address
+        let mut bank =
+            Bankswitch::new(&nsf(0x8123, [0, 1, 2, 3, 4, 5, 6, 7], 0, 0x2000));
+        assert_eq!(bank.banks(), 3);
+        assert_eq!(bank.get(0x8122), Ok(0x00));
+        assert_eq!(bank.get(0x8123), Ok(0x00));
+        assert_eq!(bank.get(0x9000), Ok(0x0E));
+        assert_eq!(bank.get(0xA122), Ok(0x1F));
+        // not bankswitched, the data sits at the load address
+        let mut bank = Bankswitch::new(&nsf(0x8123, [0; 8], 0, 0x200));
+        assert!(!bank.switched());
+        assert_eq!(bank.get(0x8123), Ok(0x00));
+        assert_eq!(bank.get(0x8223), Ok(0x01));
+        assert_eq!(bank.get(0xFFFF), Ok(0x00));
+    }
+
+    #[test]
+    fn remap() {
+        let mut bank =
+            Bankswitch::new(&nsf(0x8000, [0, 1, 2, 3, 4, 5, 6, 7], 0, 0x3000));
+        assert_eq!(bank.get(0x9000), Ok(0x10));
+        bank.set(REGISTERS, 2).unwrap();
+        assert_eq!(bank.get(0x8000), Ok(0x20));
+        // out of range banks wrap around
+        bank.set(0x5FFF, 4).unwrap();
+        assert_eq!(bank.get(0xF000), Ok(0x10));
+        assert_eq!(
+            bank.get(REGISTERS),
+            Err(AddressError::WriteOnly(REGISTERS))
+        );
+        bank.reset();
+        assert_eq!(bank.get(0x8000), Ok(0x00));
+        assert_eq!(bank.set(0x8000, 0), Err(AddressError::ReadOnly(0x8000)));
+        // the registers do nothing without bankswitching
+        let mut bank = Bankswitch::new(&nsf(0x8000, [0; 8], 0, 0x3000));
+        bank.set(REGISTERS, 2).unwrap();
+        assert_eq!(bank.get(0x8000), Ok(0x00));
+    }
+
+    #[test]
+    fn fds() {
+        let banks = [0, 1, 2, 3, 4, 5, 1, 2];
+        let mut bank = Bankswitch::new(&nsf(0x8000, banks, 0b100, 0x6000));
+        // $6000 and $7000 start with the banks of $E000 and $F000
+        assert_eq!(bank.get(0x6000), Ok(0x10));
+        assert_eq!(bank.get(0x7000), Ok(0x20));
+        assert_eq!(bank.get(0xE000), Ok(0x10));
+        // a switch copies the bank into RAM
+        bank.set(0x8000, 0xAA).unwrap();
+        bank.set(FDS_REGISTERS, 5).unwrap();
+        assert_eq!(bank.get(0x6000), Ok(0x50));
+        bank.set(0x6000, 0xBB).unwrap();
+        assert_eq!(bank.get(0x6000), Ok(0xBB));
+        assert_eq!(bank.get(0x8000), Ok(0xAA));
+        bank.set(0x5FF7, 0).unwrap();
+        assert_eq!(bank.get(0x7000), Ok(0x00));
+        // $E000-$FFFF is not writable
+        assert_eq!(bank.set(0xE000, 0), Err(AddressError::ReadOnly(0xE000)));
+        assert_eq!(bank.set(0xDFFF, 0xCC), Ok(()));
+        assert_eq!(
+            bank.get(FDS_REGISTERS),
+            Err(AddressError::WriteOnly(FDS_REGISTERS))
+        );
+        bank.reset();
+        assert_eq!(bank.get(0x6000), Ok(0x10));
+        assert_eq!(bank.get(0x8000), Ok(0x00));
+    }
+}
@@ -0,0 +1,578 @@
+//! NSF header
+//!
+//! [`Nsf::parse`] reads the 128-byte header of a plain NSF file into a
+//! [`Header`] and keeps the program data following it. The header is checked
+//! for what a player cannot recover from: a short file, a wrong magic, code
+//! loaded below $8000 without bankswitching and a starting song outside of
+//! the song count. NSFe files are handed to [`nsfe::parse`](crate::nsf::nsfe)
+//! and come out as the same [`Nsf`].
+//!
+//! NSF2 (version 2) uses the last header bytes for [`Features`] and the
+//! length of the program data. When that length is set, the data is followed
+//! by a metadata block of NSFe chunks. See <https://www.nesdev.org/wiki/NSF2>.
+
+use crate::nsf::nsfe::{self, Metadata};
+
+/// magic at the start of every NSF file
+pub const MAGIC: [u8; 5] = *b"NESM\x1A";
+/// size of the NSF header, the program data follows it
+pub const HEADER_SIZE: usize = 0x80;
+/// standard NTSC play period in microseconds, 60.1 Hz
+pub const NTSC_SPEED: u16 = 16639;
+/// standard PAL play period in microseconds, 50.0 Hz
+pub const PAL_SPEED: u16 = 19997;
+
+/// NsfError represents an error that can occur when parsing an NSF file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NsfError {
+    /// the file ends after the indecated number of bytes, before the header
+    /// or the program data it announces is complete.
+    Truncated(usize),
+    /// the file does not start with the `NESM\x1A` magic.
+    BadMagic,
+    /// the indecated load address is below $8000 ($6000 for FDS) and the
+    /// file is not bankswitched.
+    LoadAddress(u16),
+    /// the starting song is not between 1 and the song count.
+    StartingSong { start: u8, total: u8 },
+    /// the NSFe file lacks the indecated mandatory chunk, or has it after a
+    /// chunk that depends on it.
+    MissingChunk([u8; 4]),
+    /// the indecated NSFe chunk must be understood but is not supported.
+    UnknownChunk([u8; 4]),
+    /// the indecated NSFe chunk is malformed or repeated.
+    BadChunk([u8; 4]),
+}
+
+/// Region represents the TV systems an NSF is made for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// NTSC only
+    Ntsc,
+    /// PAL only
+    Pal,
+    /// both, the play speed of the system is used
+    Dual,
+}
+
+/// Chips represents the expansion sound chips used by an NSF
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chips {
+    pub chips: u8,
+}
+
+/// Features represents the NSF2 feature flags
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    pub features: u8,
+}
+
+/// Header represents the fields of an NSF header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// format version, 1 for NSF and 2 for NSF2
+    pub version: u8,
+    /// number of songs
+    pub songs: u8,
+    /// first song to play, counting from 1
+    pub start: u8,
+    /// address the program data is loaded at
+    pub load: u16,
+    /// address of the INIT routine
+    pub init: u16,
+    /// address of the PLAY routine
+    pub play: u16,
+    pub title: String,
+    pub artist: String,
+    pub copyright: String,
+    /// NTSC play period in microseconds
+    pub ntsc_speed: u16,
+    /// initial banks of $8000-$FFFF, all zero when not bankswitched
+    pub banks: [u8; 8],
+    /// PAL play period in microseconds
+    pub pal_speed: u16,
+    pub region: Region,
+    pub chips: Chips,
+    /// NSF2 feature flags, clear before version 2
+    pub features: Features,
+    /// NSF2 length of the program data, 0 when it runs to the end of the
+    /// file
+    pub data_len: u32,
+}
+
+/// Nsf represents a parsed NSF file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nsf {
+    pub header: Header,
+    /// program data, loaded at `header.load`
+    pub data: Vec<u8>,
+    /// NSFe metadata, empty for a plain NSF
+    pub meta: Metadata,
+}
+
+/// little-endian word at an offset of the header
+fn word(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+/// 32-byte string field at an offset of the header, up to the first NUL
+fn text(bytes: &[u8], at: usize) -> String {
+    let field = &bytes[at..at + 32];
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..len]).into_owned()
+}
+
+// Common methods for Chips
+impl Chips {
+    /// check the Konami VRC6
+    pub fn vrc6(&self) -> bool {
+        self.chips & 0b0000_0001 != 0
+    }
+
+    /// check the Konami VRC7
+    pub fn vrc7(&self) -> bool {
+        self.chips & 0b0000_0010 != 0
+    }
+
+    /// check the Famicom Disk System
+    pub fn fds(&self) -> bool {
+        self.chips & 0b0000_0100 != 0
+    }
+
+    /// check the Nintendo MMC5
+    pub fn mmc5(&self) -> bool {
+        self.chips & 0b0000_1000 != 0
+    }
+
+    /// check the Namco 163
+    pub fn n163(&self) -> bool {
+        self.chips & 0b0001_0000 != 0
+    }
+
+    /// check the Sunsoft 5B
+    pub fn s5b(&self) -> bool {
+        self.chips & 0b0010_0000 != 0
+    }
+
+    /// check the VT02+
+    pub fn vt02(&self) -> bool {
+        self.chips & 0b0100_0000 != 0
+    }
+}
+
+// Common methods for Features
+impl Features {
+    /// check whether the IRQ vector at $FFFE-$FFFF is writable and IRQs
+    /// are serviced
+    pub fn irq(&self) -> bool {
+        self.features & 0b0001_0000 != 0
+    }
+
+    /// check whether INIT may never return, PLAY then interrupts it
+    pub fn non_returning_init(&self) -> bool {
+        self.features & 0b0010_0000 != 0
+    }
+
+    /// check whether PLAY is never called
+    pub fn suppress_play(&self) -> bool {
+        self.features & 0b0100_0000 != 0
+    }
+
+    /// check whether the metadata block must be understood to play the file
+    pub fn metadata_required(&self) -> bool {
+        self.features & 0b1000_0000 != 0
+    }
+}
+
+impl Header {
+    /// parse and validate the header at the start of an NSF file
+    pub fn parse(bytes: &[u8]) -> Result<Header, NsfError> {
+        if !MAGIC.starts_with(&bytes[..bytes.len().min(MAGIC.len())]) {
+            return Err(NsfError::BadMagic);
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(NsfError::Truncated(bytes.len()));
+        }
+        let mut banks = [0; 8];
+        banks.copy_from_slice(&bytes[0x70..0x78]);
+        let region = match bytes[0x7A] & 0b11 {
+            0b00 => Region::Ntsc,
+            0b01 => Region::Pal,
+            _ => Region::Dual,
+        };
+        // reserved before NSF2, old rips may have garbage there
+        let (features, data_len) = match bytes[0x05] {
+            0 | 1 => (Features::default(), 0),
+            _ => (
+                Features {
+                    features: bytes[0x7C],
+                },
+                u32::from_le_bytes([bytes[0x7D], bytes[0x7E], bytes[0x7F], 0]),
+            ),
+        };
+        let header = Header {
+            version: bytes[0x05],
+            songs: bytes[0x06],
+            start: bytes[0x07],
+            load: word(bytes, 0x08),
+            init: word(bytes, 0x0A),
+            play: word(bytes, 0x0C),
+            title: text(bytes, 0x0E),
+            artist: text(bytes, 0x2E),
+            copyright: text(bytes, 0x4E),
+            ntsc_speed: word(bytes, 0x6E),
+            banks,
+            pal_speed: word(bytes, 0x78),
+            region,
+            chips: Chips { chips: bytes[0x7B] },
+            features,
+            data_len,
+        };
+        header.validate()?;
+        Ok(header)
+    }
+
+    /// check the fields a player depends on. An FDS tune may load down to
+    /// $6000, which is RAM on the disk system.
+    pub fn validate(&self) -> Result<(), NsfError> {
+        let lowest = if self.chips.fds() { 0x6000 } else { 0x8000 };
+        if self.load < lowest && !self.bankswitched() {
+            return Err(NsfError::LoadAddress(self.load));
+        }
+        if self.start == 0 || self.start > self.songs {
+            return Err(NsfError::StartingSong {
+                start: self.start,
+                total: self.songs,
+            });
+        }
+        Ok(())
+    }
+
+    /// check whether the program data is bankswitched
+    pub fn bankswitched(&self) -> bool {
+        self.banks != [0; 8]
+    }
+}
+
+impl Nsf {
+    /// parse an NSF or NSFe file
+    pub fn parse(bytes: &[u8]) -> Result<Nsf, NsfError> {
+        if bytes.starts_with(&nsfe::MAGIC) {
+            return nsfe::parse(bytes);
+        }
+        let mut header = Header::parse(bytes)?;
+        let end = match header.data_len {
+            0 => bytes.len(),
+            len => HEADER_SIZE + len as usize,
+        };
+        if bytes.len() == HEADER_SIZE || bytes.len() < end {
+            return Err(NsfError::Truncated(bytes.len()));
+        }
+        let mut updated = header.clone();
+        let meta = match nsfe::metadata(bytes, end, &mut updated) {
+            Ok(meta) => {
+                header = updated;
+                meta
+            }
+            // an optional block is skipped when it cannot be read
+            Err(_) if !header.features.metadata_required() => {
+                nsfe::metadata(bytes, bytes.len(), &mut header)?
+            }
+            Err(e) => return Err(e),
+        };
+        header.validate()?;
+        Ok(Nsf {
+            header,
+            data: bytes[HEADER_SIZE..end].to_vec(),
+            meta,
+        })
+    }
+}
+
+// Stringfy the NsfError
+impl std::fmt::Display for NsfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NsfError::Truncated(len) => {
+                write!(f, "<NsfError> File truncated at {} bytes", len)
+            }
+            NsfError::BadMagic => write!(f, "<NsfError> Bad magic"),
+            NsfError::LoadAddress(addr) => write!(
+                f,
+                "<NsfError> Load address ${:04X} too low without \
+                 bankswitching",
+                addr
+            ),
+            NsfError::StartingSong { start, total } => write!(
+                f,
+                "<NsfError> Starting song {} out of 1-{}",
+                start, total
+            ),
+            NsfError::MissingChunk(id) => write!(
+                f,
+                "<NsfError> Missing chunk {}",
+                String::from_utf8_lossy(id)
+            ),
+            NsfError::UnknownChunk(id) => write!(
+                f,
+                "<NsfError> Unknown required chunk {}",
+                String::from_utf8_lossy(id)
+            ),
+            NsfError::BadChunk(id) => write!(
+                f,
+                "<NsfError> Bad chunk {}",
+                String::from_utf8_lossy(id)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NsfError {}
+
+/// NSF file assembled field by field for tests
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct TestNsf {
+    pub bytes: Vec<u8>,
+}
+
+#[cfg(test)]
+impl TestNsf {
+    /// header of a one-song NTSC tune loaded at $8000, INIT and PLAY at the
+    /// load address, without program data
+    pub fn new() -> TestNsf {
+        let mut bytes = vec![0; HEADER_SIZE];
+        bytes[..5].copy_from_slice(&MAGIC);
+        bytes[0x05] = 1;
+        bytes[0x06] = 1;
+        bytes[0x07] = 1;
+        bytes[0x08..0x0E]
+            .copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+        bytes[0x6E..0x70].copy_from_slice(&NTSC_SPEED.to_le_bytes());
+        bytes[0x78..0x7A].copy_from_slice(&PAL_SPEED.to_le_bytes());
+        TestNsf { bytes }
+    }
+
+    /// overwrite the bytes at an offset of the file
+    pub fn with(mut self, at: usize, val: &[u8]) -> TestNsf {
+        self.bytes[at..at + val.len()].copy_from_slice(val);
+        self
+    }
+
+    /// set the song count and the starting song
+    pub fn with_songs(self, songs: u8, start: u8) -> TestNsf {
+        self.with(0x06, &[songs, start])
+    }
+
+    /// set the load, INIT and PLAY addresses
+    pub fn with_addresses(self, load: u16, init: u16, play: u16) -> TestNsf {
+        let [load, init, play] =
+            [load, init, play].map(|addr| addr.to_le_bytes());
+        self.with(0x08, &[load, init, play].concat())
+    }
+
+    /// set the initial banks
+    pub fn with_banks(self, banks: [u8; 8]) -> TestNsf {
+        self.with(0x70, &banks)
+    }
+
+    /// set the expansion chip bits
+    pub fn with_chips(self, chips: u8) -> TestNsf {
+        self.with(0x7B, &[chips])
+    }
+
+    /// make the file NSF2 with the feature flags set
+    pub fn with_features(self, features: u8) -> TestNsf {
+        self.with(0x05, &[2]).with(0x7C, &[features])
+    }
+
+    /// append program data
+    pub fn with_data(mut self, data: &[u8]) -> TestNsf {
+        self.bytes.extend_from_slice(data);
+        self
+    }
+
+    /// parse the file, panicking on an error
+    pub fn nsf(&self) -> Nsf {
+        Nsf::parse(&self.bytes).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// header of a three-song tune with INIT at $8003 and PLAY at $8006
+    fn header() -> TestNsf {
+        TestNsf::new()
+            .with_songs(3, 1)
+            .with_addresses(0x8000, 0x8003, 0x8006)
+    }
+
+    #[test]
+    fn parse() {
+        let nsf = header()
+            .with(0x0E, b"Title")
+            .with(0x7A, &[0b10])
+            .with_data(&[0x60])
+            .nsf();
+        assert_eq!(nsf.header.songs, 3);
+        assert_eq!(nsf.header.load, 0x8000);
+        assert_eq!(nsf.header.init, 0x8003);
+        assert_eq!(nsf.header.play, 0x8006);
+        assert_eq!(nsf.header.title, "Title");
+        assert_eq!(nsf.header.artist, "");
+        assert_eq!(nsf.header.ntsc_speed, NTSC_SPEED);
+        assert_eq!(nsf.header.pal_speed, PAL_SPEED);
+        assert_eq!(nsf.header.region, Region::Dual);
+        assert!(!nsf.header.bankswitched());
+        assert_eq!(nsf.data, [0x60]);
+        assert_eq!(nsf.meta.tracks.len(), 3);
+    }
+
+    #[test]
+    fn chips() {
+        let chips = |bits: u8| {
+            let tune = header().with_chips(bits).with_data(&[0x60]);
+            tune.nsf().header.chips
+        };
+        let all = |chips: Chips| {
+            [
+                chips.vrc6(),
+                chips.vrc7(),
+                chips.fds(),
+                chips.mmc5(),
+                chips.n163(),
+                chips.s5b(),
+                chips.vt02(),
+            ]
+        };
+        for bit in 0..7 {
+            let want: Vec<bool> = (0..7).map(|i| i == bit).collect();
+            assert_eq!(all(chips(1 << bit)).to_vec(), want);
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = header().bytes;
+        assert_eq!(Nsf::parse(&bytes[..0x7F]), Err(NsfError::Truncated(0x7F)));
+        // a header without program data
+        assert_eq!(Nsf::parse(&bytes), Err(NsfError::Truncated(0x80)));
+    }
+
+    #[test]
+    fn bad_magic() {
+        let bytes = header().with(3, b"X").with_data(&[0x60]).bytes;
+        assert_eq!(Nsf::parse(&bytes), Err(NsfError::BadMagic));
+        assert_eq!(Nsf::parse(b"NESN"), Err(NsfError::BadMagic));
+        // a prefix of the magic is a short file
+        assert_eq!(Nsf::parse(b"NES"), Err(NsfError::Truncated(3)));
+    }
+
+    #[test]
+    fn load_address() {
+        let tune = header().with_data(&[0x60]);
+        let tune = tune.with_addresses(0x7FFF, 0x8003, 0x8006);
+        assert_eq!(Nsf::parse(&tune.bytes), Err(NsfError::LoadAddress(0x7FFF)));
+        // FDS RAM starts at $6000
+        let tune = tune.with_chips(0b0000_0100);
+        assert!(Nsf::parse(&tune.bytes).is_ok());
+        let tune = tune.with_addresses(0x5FFF, 0x8003, 0x8006);
+        assert_eq!(Nsf::parse(&tune.bytes), Err(NsfError::LoadAddress(0x5FFF)));
+        // bankswitching maps the data by banks
+        let tune = tune.with_chips(0).with_banks([0, 1, 2, 3, 4, 5, 6, 7]);
+        assert!(Nsf::parse(&tune.bytes).is_ok());
+    }
+
+    #[test]
+    fn starting_song() {
+        let tune = header().with_data(&[0x60]);
+        for (start, want) in [
+            (0, Err(NsfError::StartingSong { start: 0, total: 3 })),
+            (4, Err(NsfError::StartingSong { start: 4, total: 3 })),
+            (1, Ok(())),
+            (3, Ok(())),
+        ] {
+            let result = Nsf::parse(&tune.clone().with_songs(3, start).bytes);
+            assert_eq!(result.map(|_| ()), want, "start {}", start);
+        }
+    }
+
+    #[test]
+    fn features() {
+        let features = |bits: u8| {
+            let tune = header().with_features(bits).with_data(&[0x60]);
+            let features = tune.nsf().header.features;
+            [
+                features.irq(),
+                features.non_returning_init(),
+                features.suppress_play(),
+                features.metadata_required(),
+            ]
+        };
+        for (bits, want) in [
+            (0b0000_0000, [false, false, false, false]),
+            (0b0001_0000, [true, false, false, false]),
+            (0b0010_0000, [false, true, false, false]),
+            (0b0100_0000, [false, false, true, false]),
+            (0b1000_0000, [false, false, false, true]),
+            (0b1111_0000, [true, true, true, true]),
+            // the low bits are not features
+            (0b0000_1111, [false, false, false, false]),
+        ] {
+            assert_eq!(features(bits), want, "{:08b}", bits);
+        }
+    }
+
+    #[test]
+    fn features_ignored_before_nsf2() {
+        let tune = header().with(0x7C, &[0xFF, 0x10]).with_data(&[0x60]);
+        let header = tune.nsf().header;
+        assert_eq!(header.features, Features::default());
+        assert_eq!(header.data_len, 0);
+    }
+
+    /// NSF2 file with one byte of program data and a metadata block
+    fn nsf2(flags: u8, block: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut tune = header().with_features(flags).with(0x7D, &[1]);
+        tune = tune.with_data(&[0x60]);
+        for (id, data) in block {
+            tune = tune
+                .with_data(&(data.len() as u32).to_le_bytes())
+                .with_data(*id)
+                .with_data(data);
+        }
+        tune.bytes
+    }
+
+    #[test]
+    fn metadata_block() {
+        let bytes = nsf2(0, &[(b"auth", b"Title\0"), (b"tlbl", b"One\0")]);
+        let nsf = Nsf::parse(&bytes).unwrap();
+        assert_eq!(nsf.header.data_len, 1);
+        assert_eq!(nsf.data, [0x60]);
+        assert_eq!(nsf.header.title, "Title");
+        assert_eq!(nsf.meta.tracks[0].name.as_deref(), Some("One"));
+        // the length must fit the file
+        let mut bytes = nsf2(0, &[]);
+        bytes[0x7D] = 2;
+        assert_eq!(Nsf::parse(&bytes), Err(NsfError::Truncated(0x81)));
+    }
+
+    #[test]
+    fn metadata_fallback() {
+        let block: &[(&[u8; 4], &[u8])] =
+            &[(b"auth", b"Title\0"), (b"ZZZZ", &[])];
+        // an optional block that cannot be read is skipped
+        let nsf = Nsf::parse(&nsf2(0, block)).unwrap();
+        assert_eq!(nsf.data, [0x60]);
+        assert_eq!(nsf.header.title, "");
+        assert_eq!(nsf.meta.tracks.len(), 3);
+        assert_eq!(nsf.meta.ripper, None);
+        assert_eq!(
+            Nsf::parse(&nsf2(0b1000_0000, block)),
+            Err(NsfError::UnknownChunk(*b"ZZZZ"))
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! NSF machine
+//!
+//! [`NsfMachine`] is the hardware an NSF player emulates: the cpu, 2 KiB of
+//! RAM, the APU registers, 8 KiB of work RAM at $6000-$7FFF and the program
+//! data behind its [`Bankswitch`]. [`NsfMachine::start`] brings it into the
+//! state the NSF spec requires before INIT and calls INIT, so every track
+//! starts from the same state:
+//!
+//! 1. $0000-$07FF and $6000-$7FFF are cleared.
+//! 2. $4000-$4013 are written with $00, $4015 with $00 then $0F and $4017
+//!    with $40.
+//! 3. The initial banks are mapped again, which also reloads FDS RAM.
+//! 4. A fresh cpu gets SP = $FF, A = song and X = 0 for NTSC or 1 for PAL
+//!    and INIT is called.
+//!
+//! See <https://www.nesdev.org/wiki/NSF#Initializing_a_tune>. The APU is not
+//! emulated, its registers only keep the last value written and $4015 reads
+//! back the channels it enabled. Reads of write-only and unmapped addresses
+//! are ignored by the [`FaultPolicy::LENIENT`] policy of the cpu, which is
+//! why the bus does not need an [`OpenBus`](crate::op65::openbus::OpenBus).
+//! With NSF2 IRQ support the IRQ vector and the IRQ timer at $401B-$401D of
+//! the [`IrqVector`] are mapped as well.
+//!
+//! [`NsfBus`] decodes its addresses itself rather than being a
+//! [`MemoryMap`](crate::op65::memmap::MemoryMap): every start has to clear
+//! the RAM, reset the APU registers and remap the banks, and the IRQ timer
+//! is clocked by the player, but the regions of a map own their storage and
+//! devices and cannot be reached once it is built.
+
+use crate::nsf::bankswitch::{Bankswitch, FDS_REGISTERS};
+use crate::nsf::header::Nsf;
+use crate::nsf::player::{IrqLine, IrqVector, Player};
+use crate::nsf::player::{IRQ_CONTROL, IRQ_TIMER, NTSC_CLOCK};
+use crate::op65::context::{AddressError, BusMut, FaultPolicy};
+use crate::op65::cpu::{CallError, IRQ_VECTOR};
+
+/// cycles INIT may run before it times out, one second
+pub const INIT_BUDGET: u64 = NTSC_CLOCK;
+/// cycles PLAY may run before it times out, one second
+pub const PLAY_BUDGET: u64 = NTSC_CLOCK;
+/// first APU register
+pub const APU_REGISTERS: u16 = 0x4000;
+/// APU status register, the only readable one
+pub const APU_STATUS: u16 = 0x4015;
+/// number of APU registers, $4000-$4017
+const APU_SIZE: usize = 0x18;
+
+/// NsfBus is the address space of an NSF player
+pub struct NsfBus {
+    /// 2 KiB RAM mirrored across $0000-$1FFF
+    ram: Vec<u8>,
+    /// work RAM at $6000-$7FFF, FDS tunes use the RAM of the bankswitch
+    wram: Vec<u8>,
+    /// last values written to $4000-$4017
+    apu: [u8; APU_SIZE],
+    /// program data, with the IRQ vector of NSF2 IRQ support
+    cart: IrqVector<Bankswitch>,
+    /// the IRQ vector is writable
+    irq: bool,
+    /// FDS tune, $6000-$7FFF belongs to the bankswitch
+    fds: bool,
+}
+
+/// NsfMachine plays the tracks of an NSF
+pub struct NsfMachine {
+    nsf: Nsf,
+    bus: NsfBus,
+    player: Player,
+    pal: bool,
+}
+
+impl NsfBus {
+    /// address space with the program data of an NSF
+    pub fn new(nsf: &Nsf) -> NsfBus {
+        NsfBus {
+            ram: vec![0; 0x800],
+            wram: vec![0; 0x2000],
+            apu: [0; APU_SIZE],
+            cart: IrqVector::new(Bankswitch::new(nsf)),
+            irq: nsf.header.features.irq(),
+            fds: nsf.header.chips.fds(),
+        }
+    }
+
+    /// last values written to the APU registers $4000-$4017
+    pub fn apu(&self) -> &[u8; APU_SIZE] {
+        &self.apu
+    }
+
+    /// program data
+    pub fn bankswitch(&self) -> &Bankswitch {
+        self.cart.inner()
+    }
+
+    /// bring the memory and APU registers into the state before INIT
+    fn reset(&mut self) {
+        self.ram.fill(0);
+        self.wram.fill(0);
+        for addr in APU_REGISTERS..=0x4013 {
+            self.write_apu(addr, 0x00);
+        }
+        self.write_apu(APU_STATUS, 0x00);
+        self.write_apu(APU_STATUS, 0x0F);
+        self.write_apu(0x4017, 0x40);
+        self.cart.clear();
+        self.cart.inner_mut().reset();
+    }
+
+    /// APU status, the channels enabled by the last write to $4015 count as
+    /// playing
+    fn status(&self) -> u8 {
+        self.apu[(APU_STATUS - APU_REGISTERS) as usize] & 0x1F
+    }
+
+    /// write an APU register
+    fn write_apu(&mut self, addr: u16, val: u8) {
+        self.apu[(addr - APU_REGISTERS) as usize] = val;
+    }
+}
+
+impl NsfMachine {
+    /// create a machine for an NSF. `pal` selects the system a dual-region
+    /// tune plays on.
+    pub fn new(nsf: Nsf, pal: bool) -> NsfMachine {
+        NsfMachine {
+            bus: NsfBus::new(&nsf),
+            player: Player::new(&nsf.header, pal),
+            nsf,
+            pal,
+        }
+    }
+
+    /// NSF being played
+    pub fn nsf(&self) -> &Nsf {
+        &self.nsf
+    }
+
+    /// address space of the machine
+    pub fn bus(&self) -> &NsfBus {
+        &self.bus
+    }
+
+    /// address space of the machine, for mutation
+    pub fn bus_mut(&mut self) -> &mut NsfBus {
+        &mut self.bus
+    }
+
+    /// player of the current track
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    /// reset the machine and call INIT for a song, counting from 0, and
+    /// return the cycles INIT took
+    pub fn start(&mut self, song: u8) -> Result<u64, CallError> {
+        self.bus.reset();
+        self.player = Player::new(&self.nsf.header, self.pal);
+        let cpu = self.player.cpu_mut();
+        cpu.set_policy(FaultPolicy::LENIENT);
+        cpu.reg.sp = 0xFF;
+        self.player.init(&mut self.bus, song, INIT_BUDGET)
+    }
+
+    /// run one play period of the current track and return its cycles
+    pub fn frame(&mut self) -> Result<u64, CallError> {
+        self.player.frame(&mut self.bus, PLAY_BUDGET)
+    }
+}
+
+// BusMut for NsfBus
+impl BusMut for NsfBus {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        match addr {
+            0x0000..=0x1FFF => Ok(self.ram[addr as usize % 0x800]),
+            APU_STATUS => Ok(self.status()),
+            0x4000..=0x4017 => Err(AddressError::WriteOnly(addr)),
+            IRQ_TIMER..=IRQ_CONTROL if self.irq => {
+                Err(AddressError::WriteOnly(addr))
+            }
+            0x6000..=0x7FFF if !self.fds => {
+                Ok(self.wram[(addr - 0x6000) as usize])
+            }
+            FDS_REGISTERS..=0xFFFF => self.cart.get(addr),
+            _ => Err(AddressError::Unavailable(addr)),
+        }
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram[addr as usize % 0x800] = val;
+                Ok(())
+            }
+            0x4000..=0x4017 => {
+                self.write_apu(addr, val);
+                Ok(())
+            }
+            IRQ_TIMER..=IRQ_CONTROL if self.irq => self.cart.set(addr, val),
+            0x6000..=0x7FFF if !self.fds => {
+                self.wram[(addr - 0x6000) as usize] = val;
+                Ok(())
+            }
+            IRQ_VECTOR..=0xFFFF if !self.irq => {
+                Err(AddressError::ReadOnly(addr))
+            }
+            FDS_REGISTERS..=0xFFFF => self.cart.set(addr, val),
+            _ => Err(AddressError::Unavailable(addr)),
+        }
+    }
+}
+
+// IrqLine for NsfBus, the IRQ timer of the IrqVector
+impl IrqLine for NsfBus {
+    fn clock(&mut self, cycles: u32) -> bool {
+        self.irq && self.cart.clock(cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nsf::header::TestNsf;
+    use AddressError::*;
+
+    /// INIT of the test tunes, storing SP at $00, A at $01 and X at $02
+    const INIT: [u8; 8] = [0x85, 0x01, 0x86, 0x02, 0xBA, 0x86, 0x00, 0x60];
+
+    /// bankswitched two-song tune for both regions. Bank 0 holds INIT, bank
+    /// 1 is filled with $11 and is mapped at $9000 and $E000-$FFFF.
+    fn tune(chips: u8) -> TestNsf {
+        let mut data = vec![0; 0x2000];
+        data[..INIT.len()].copy_from_slice(&INIT);
+        data[0x1000..].fill(0x11);
+        TestNsf::new()
+            .with_songs(2, 1)
+            .with(0x7A, &[0b10])
+            .with_banks([0, 1, 0, 0, 0, 0, 1, 1])
+            .with_chips(chips)
+            .with_data(&data)
+    }
+
+    #[test]
+    fn init_registers() {
+        for (pal, song) in [(false, 0), (true, 1)] {
+            let mut machine = NsfMachine::new(tune(0).nsf(), pal);
+            machine.start(song).unwrap();
+            let bus = machine.bus_mut();
+            // SP is $FF before the return address is pushed
+            assert_eq!(bus.get(0x00), Ok(0xFD));
+            assert_eq!(bus.get(0x01), Ok(song));
+            assert_eq!(bus.get(0x02), Ok(pal as u8));
+        }
+    }
+
+    #[test]
+    fn memory_cleared() {
+        let mut machine = NsfMachine::new(tune(0).nsf(), false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        let ram = [0x0003, 0x07FF, 0x6000, 0x7FFF];
+        for addr in ram {
+            bus.set(addr, 0xAA).unwrap();
+        }
+        for addr in APU_REGISTERS..=0x4017 {
+            bus.set(addr, 0xFF).unwrap();
+        }
+        machine.start(1).unwrap();
+        let bus = machine.bus_mut();
+        for addr in ram {
+            assert_eq!(bus.get(addr), Ok(0x00), "${:04X}", addr);
+        }
+        // $4014 and $4016 are not APU registers and keep their value
+        let mut apu = [0; APU_SIZE];
+        apu[0x14] = 0xFF;
+        apu[0x15] = 0x0F;
+        apu[0x16] = 0xFF;
+        apu[0x17] = 0x40;
+        assert_eq!(machine.bus().apu(), &apu);
+    }
+
+    #[test]
+    fn banks_reloaded() {
+        let mut machine = NsfMachine::new(tune(0).nsf(), false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert_eq!(bus.get(0x9000), Ok(0x11));
+        bus.set(0x5FF9, 0).unwrap();
+        assert_eq!(bus.get(0x9000), Ok(0x85));
+        machine.start(0).unwrap();
+        assert_eq!(machine.bus_mut().get(0x9000), Ok(0x11));
+
+        // FDS RAM is loaded from the initial banks again
+        let mut machine = NsfMachine::new(tune(0b100).nsf(), false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert_eq!(bus.get(0x6000), Ok(0x11));
+        bus.set(0x6000, 0xAA).unwrap();
+        bus.set(0x5FF7, 0).unwrap();
+        assert_eq!(bus.get(0x7000), Ok(0x85));
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert_eq!(bus.get(0x6000), Ok(0x11));
+        assert_eq!(bus.get(0x7000), Ok(0x11));
+    }
+
+    #[test]
+    fn apu_status() {
+        let mut machine = NsfMachine::new(tune(0).nsf(), false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert_eq!(bus.get(APU_STATUS), Ok(0x0F));
+        bus.set(APU_STATUS, 0xFF).unwrap();
+        assert_eq!(bus.get(APU_STATUS), Ok(0x1F));
+        assert_eq!(bus.get(0x4000), Err(WriteOnly(0x4000)));
+        assert_eq!(bus.get(0x4014), Err(WriteOnly(0x4014)));
+        assert_eq!(bus.get(0x4016), Err(WriteOnly(0x4016)));
+        assert_eq!(bus.get(0x4017), Err(WriteOnly(0x4017)));
+    }
+
+    #[test]
+    fn irq_timer() {
+        let nsf = tune(0).with_features(0b0001_0000).nsf();
+        let mut machine = NsfMachine::new(nsf, false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        bus.set(IRQ_TIMER, 9).unwrap();
+        bus.set(IRQ_CONTROL, 1).unwrap();
+        assert!(!bus.clock(9));
+        assert!(bus.clock(1));
+        assert_eq!(bus.get(IRQ_CONTROL), Err(WriteOnly(IRQ_CONTROL)));
+        bus.set(IRQ_VECTOR, 0x34).unwrap();
+        assert_eq!(bus.get(IRQ_VECTOR), Ok(0x34));
+        // a new start stops the timer and forgets the vector
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert!(!bus.clock(100));
+        assert_eq!(bus.get(IRQ_VECTOR), Ok(0x11));
+
+        // without IRQ support the registers are not mapped
+        let mut machine = NsfMachine::new(tune(0).nsf(), false);
+        machine.start(0).unwrap();
+        let bus = machine.bus_mut();
+        assert_eq!(bus.set(IRQ_TIMER, 9), Err(Unavailable(IRQ_TIMER)));
+        assert_eq!(bus.set(IRQ_VECTOR, 0), Err(ReadOnly(IRQ_VECTOR)));
+        assert!(!bus.clock(100));
+    }
+}
@@ -0,0 +1,11 @@
+//! NES Sound Format
+//!
+//! An NSF file is the sound driver and music data ripped from a NES game,
+//! with a header telling a player where to load the code and which routines
+//! to call. See <https://www.nesdev.org/wiki/NSF>.
+
+pub mod bankswitch;
+pub mod header;
+pub mod machine;
+pub mod nsfe;
+pub mod player;
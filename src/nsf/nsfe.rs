@@ -0,0 +1,453 @@
+//! NSFe
+//!
+//! An NSFe file is `NSFE` followed by chunks, each a 32-bit little-endian
+//! length, a four-character id and the chunk data. INFO, DATA and NEND are
+//! mandatory, the others add metadata a plain NSF has no room for. A chunk
+//! whose id starts with an uppercase letter must be understood by the reader,
+//! any other unknown chunk is skipped. See <https://www.nesdev.org/wiki/NSFe>.
+//!
+//! [`parse`] maps the file onto the same [`Nsf`] as a plain NSF, the
+//! metadata without a place in the [`Header`] goes to [`Metadata`]. The
+//! metadata block of an NSF2 file holds the same chunks and is read by
+//! [`metadata`].
+
+use std::time::Duration;
+
+use crate::nsf::header::{Chips, Features, Header, Nsf, NsfError, Region};
+use crate::nsf::header::{NTSC_SPEED, PAL_SPEED};
+
+/// magic at the start of every NSFe file
+pub const MAGIC: [u8; 4] = *b"NSFE";
+
+/// Track represents the metadata of a song
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Track {
+    /// name of the song (tlbl)
+    pub name: Option<String>,
+    /// length of the song before the fade (time)
+    pub time: Option<Duration>,
+    /// length of the fade out (fade)
+    pub fade: Option<Duration>,
+}
+
+/// Metadata represents the NSFe chunks beyond the header fields
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// name of the ripper (auth)
+    pub ripper: Option<String>,
+    /// one entry per song
+    pub tracks: Vec<Track>,
+    /// songs to play in order, counting from 0 (plst)
+    pub playlist: Option<Vec<u8>>,
+    /// songs that are sound effects, counting from 0 (psfx)
+    pub sfx: Vec<u8>,
+    /// free-form notes (text)
+    pub text: Option<String>,
+    /// device and volume in millibels of the mixer (mixe)
+    pub mixing: Vec<(u8, i16)>,
+    /// Dendy play period in microseconds (RATE)
+    pub dendy_speed: Option<u16>,
+    /// supported systems, bit 0 NTSC, bit 1 PAL and bit 2 Dendy (regn)
+    pub regions: Option<u8>,
+    /// system to play on when several are supported (regn)
+    pub preferred: Option<u8>,
+}
+
+/// check whether the chunk must be understood to play the file
+fn required(id: &[u8; 4]) -> bool {
+    id[0].is_ascii_uppercase()
+}
+
+/// little-endian word at an offset of a chunk
+fn word(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+/// NUL-terminated strings of a chunk, a missing last NUL ends the chunk
+fn strings(data: &[u8]) -> Vec<String> {
+    let data = data.strip_suffix(&[0]).unwrap_or(data);
+    if data.is_empty() {
+        return Vec::new();
+    }
+    data.split(|&b| b == 0)
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect()
+}
+
+/// 32-bit millisecond times of a chunk, negative values are unset
+fn times(data: &[u8]) -> Vec<Option<Duration>> {
+    data.chunks_exact(4)
+        .map(|t| i32::from_le_bytes([t[0], t[1], t[2], t[3]]))
+        .map(|ms| u64::try_from(ms).ok().map(Duration::from_millis))
+        .collect()
+}
+
+/// id and data of a chunk
+type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+/// split the chunks from an offset, up to and excluding NEND. Without
+/// `nend` the chunks may also end with the data.
+fn chunks(
+    bytes: &[u8],
+    mut at: usize,
+    nend: bool,
+) -> Result<Vec<Chunk<'_>>, NsfError> {
+    let mut chunks = Vec::new();
+    loop {
+        if bytes.len() < at + 8 {
+            return match (at == bytes.len(), nend) {
+                (true, false) => Ok(chunks),
+                (true, true) => Err(NsfError::MissingChunk(*b"NEND")),
+                _ => Err(NsfError::Truncated(bytes.len())),
+            };
+        }
+        let len = u32::from_le_bytes([
+            bytes[at],
+            bytes[at + 1],
+            bytes[at + 2],
+            bytes[at + 3],
+        ]) as usize;
+        let mut id = [0; 4];
+        id.copy_from_slice(&bytes[at + 4..at + 8]);
+        let data = bytes
+            .get(at + 8..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or(NsfError::Truncated(bytes.len()))?;
+        if &id == b"NEND" {
+            return Ok(chunks);
+        }
+        chunks.push((id, data));
+        at += 8 + len;
+    }
+}
+
+/// Reader collects the chunks describing the songs of a header
+#[derive(Default)]
+struct Reader {
+    meta: Metadata,
+    /// a BANK chunk was read
+    bank: bool,
+    names: Vec<String>,
+    time: Vec<Option<Duration>>,
+    fade: Vec<Option<Duration>>,
+}
+
+impl Reader {
+    /// apply a chunk other than INFO and DATA
+    fn chunk(
+        &mut self,
+        header: &mut Header,
+        id: [u8; 4],
+        chunk: &[u8],
+    ) -> Result<(), NsfError> {
+        let meta = &mut self.meta;
+        match &id {
+            b"BANK" => {
+                if self.bank {
+                    return Err(NsfError::BadChunk(id));
+                }
+                self.bank = true;
+                let len = chunk.len().min(8);
+                header.banks[..len].copy_from_slice(&chunk[..len]);
+            }
+            b"RATE" => {
+                if chunk.len() < 2 {
+                    return Err(NsfError::BadChunk(id));
+                }
+                header.ntsc_speed = word(chunk, 0);
+                if chunk.len() >= 4 {
+                    header.pal_speed = word(chunk, 2);
+                }
+                if chunk.len() >= 6 {
+                    meta.dendy_speed = Some(word(chunk, 4));
+                }
+            }
+            b"auth" => {
+                let mut fields = strings(chunk).into_iter();
+                header.title = fields.next().unwrap_or_default();
+                header.artist = fields.next().unwrap_or_default();
+                header.copyright = fields.next().unwrap_or_default();
+                meta.ripper = fields.next();
+            }
+            b"tlbl" => self.names = strings(chunk),
+            b"time" => self.time = times(chunk),
+            b"fade" => self.fade = times(chunk),
+            b"plst" => meta.playlist = Some(chunk.to_vec()),
+            b"psfx" => meta.sfx = chunk.to_vec(),
+            b"text" => {
+                meta.text = strings(chunk).into_iter().next();
+            }
+            b"mixe" => {
+                meta.mixing = chunk
+                    .chunks_exact(3)
+                    .map(|m| (m[0], i16::from_le_bytes([m[1], m[2]])))
+                    .collect();
+            }
+            b"regn" => {
+                if chunk.is_empty() {
+                    return Err(NsfError::BadChunk(id));
+                }
+                meta.regions = Some(chunk[0]);
+                meta.preferred = chunk.get(1).copied();
+                header.region = match chunk[0] & 0b11 {
+                    0b01 => Region::Ntsc,
+                    0b10 => Region::Pal,
+                    0b11 => Region::Dual,
+                    _ => header.region,
+                };
+            }
+            b"NSF2" => {
+                if chunk.is_empty() {
+                    return Err(NsfError::BadChunk(id));
+                }
+                // an NSFe has no metadata block, the bit does not apply
+                header.version = 2;
+                header.features = Features {
+                    features: chunk[0] & 0b0111_1111,
+                };
+            }
+            _ if required(&id) => return Err(NsfError::UnknownChunk(id)),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// metadata with one track per song
+    fn finish(self, songs: u8) -> Metadata {
+        let mut names = self.names.into_iter();
+        let mut time = self.time.into_iter();
+        let mut fade = self.fade.into_iter();
+        let tracks = (0..songs)
+            .map(|_| Track {
+                name: names.next(),
+                time: time.next().flatten(),
+                fade: fade.next().flatten(),
+            })
+            .collect();
+        Metadata {
+            tracks,
+            ..self.meta
+        }
+    }
+}
+
+/// parse an NSFe file
+pub fn parse(bytes: &[u8]) -> Result<Nsf, NsfError> {
+    if !MAGIC.starts_with(&bytes[..bytes.len().min(MAGIC.len())]) {
+        return Err(NsfError::BadMagic);
+    }
+    let mut header: Option<Header> = None;
+    let mut data: Option<Vec<u8>> = None;
+    let mut reader = Reader::default();
+    let mut pending = Vec::new();
+    for (id, chunk) in chunks(bytes, MAGIC.len(), true)? {
+        if &id == b"INFO" {
+            if chunk.len() < 9 || header.is_some() {
+                return Err(NsfError::BadChunk(id));
+            }
+            let region = match chunk[6] & 0b11 {
+                0b00 => Region::Ntsc,
+                0b01 => Region::Pal,
+                _ => Region::Dual,
+            };
+            let mut info = Header {
+                version: 1,
+                songs: chunk[8],
+                start: chunk.get(9).map_or(1, |s| s.wrapping_add(1)),
+                load: word(chunk, 0),
+                init: word(chunk, 2),
+                play: word(chunk, 4),
+                title: String::new(),
+                artist: String::new(),
+                copyright: String::new(),
+                ntsc_speed: NTSC_SPEED,
+                banks: [0; 8],
+                pal_speed: PAL_SPEED,
+                region,
+                chips: Chips { chips: chunk[7] },
+                features: Features::default(),
+                data_len: 0,
+            };
+            for (id, chunk) in pending.drain(..) {
+                reader.chunk(&mut info, id, chunk)?;
+            }
+            header = Some(info);
+            continue;
+        }
+        // every other chunk describes the songs announced by INFO, the
+        // optional ones may come first and wait for it
+        let Some(header) = header.as_mut() else {
+            if required(&id) {
+                return Err(NsfError::MissingChunk(*b"INFO"));
+            }
+            pending.push((id, chunk));
+            continue;
+        };
+        match &id {
+            // a second DATA or BANK would lay out the program twice
+            b"DATA" if data.is_some() => return Err(NsfError::BadChunk(id)),
+            b"DATA" => data = Some(chunk.to_vec()),
+            _ => reader.chunk(header, id, chunk)?,
+        }
+    }
+    let header = header.ok_or(NsfError::MissingChunk(*b"INFO"))?;
+    let data = data.ok_or(NsfError::MissingChunk(*b"DATA"))?;
+    header.validate()?;
+    let meta = reader.finish(header.songs);
+    Ok(Nsf { header, data, meta })
+}
+
+/// parse the NSF2 metadata block from an offset of the file to its end,
+/// the chunks may override fields of the header
+pub fn metadata(
+    bytes: &[u8],
+    at: usize,
+    header: &mut Header,
+) -> Result<Metadata, NsfError> {
+    let mut reader = Reader::default();
+    for (id, chunk) in chunks(bytes, at, false)? {
+        match &id {
+            b"INFO" | b"DATA" | b"NSF2" => return Err(NsfError::BadChunk(id)),
+            _ => reader.chunk(header, id, chunk)?,
+        }
+    }
+    Ok(reader.finish(header.songs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// append a chunk to a file
+    fn chunk(bytes: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(id);
+        bytes.extend(data);
+    }
+
+    /// INFO of a two-song NTSC tune loaded at $8000
+    const INFO: [u8; 10] = [0x00, 0x80, 0x03, 0x80, 0x06, 0x80, 0, 0, 2, 0];
+
+    /// NSFe file with INFO, the given chunks, DATA and NEND
+    fn nsfe(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        chunk(&mut bytes, b"INFO", &INFO);
+        for (id, data) in chunks {
+            chunk(&mut bytes, id, data);
+        }
+        chunk(&mut bytes, b"DATA", &[0x60]);
+        chunk(&mut bytes, b"NEND", &[]);
+        bytes
+    }
+
+    #[test]
+    fn nsf2_chunk() {
+        let nsf = parse(&nsfe(&[(b"NSF2", &[0b1001_0000])])).unwrap();
+        assert_eq!(nsf.header.version, 2);
+        assert!(nsf.header.features.irq());
+        assert!(!nsf.header.features.non_returning_init());
+        assert!(!nsf.header.features.metadata_required());
+        let nsf = parse(&nsfe(&[(b"NSF2", &[0b0110_0000])])).unwrap();
+        assert!(nsf.header.features.non_returning_init());
+        assert!(nsf.header.features.suppress_play());
+        assert_eq!(
+            parse(&nsfe(&[(b"NSF2", &[])])),
+            Err(NsfError::BadChunk(*b"NSF2"))
+        );
+    }
+
+    #[test]
+    fn optional_chunks_before_info() {
+        let mut bytes = MAGIC.to_vec();
+        chunk(&mut bytes, b"auth", b"Title\0Artist\0\0Ripper\0");
+        chunk(&mut bytes, b"regn", &[0b10]);
+        chunk(&mut bytes, b"INFO", &INFO);
+        chunk(&mut bytes, b"DATA", &[0x60]);
+        chunk(&mut bytes, b"NEND", &[]);
+        let nsf = parse(&bytes).unwrap();
+        assert_eq!(nsf.header.title, "Title");
+        assert_eq!(nsf.header.artist, "Artist");
+        assert_eq!(nsf.meta.ripper.as_deref(), Some("Ripper"));
+        assert_eq!(nsf.header.region, Region::Pal);
+        assert_eq!(nsf.meta.regions, Some(0b10));
+    }
+
+    #[test]
+    fn tracks() {
+        let nsf = parse(&nsfe(&[
+            (b"tlbl", b"One\0Two\0"),
+            (b"time", &[0xE8, 0x03, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]),
+            (b"fade", &[0xF4, 0x01, 0, 0]),
+            (b"plst", &[1, 0]),
+        ]))
+        .unwrap();
+        let tracks = &nsf.meta.tracks;
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].name.as_deref(), Some("One"));
+        assert_eq!(tracks[0].time, Some(Duration::from_secs(1)));
+        assert_eq!(tracks[0].fade, Some(Duration::from_millis(500)));
+        assert_eq!(tracks[1].name.as_deref(), Some("Two"));
+        assert_eq!(tracks[1].time, None);
+        assert_eq!(tracks[1].fade, None);
+        assert_eq!(nsf.meta.playlist, Some(vec![1, 0]));
+        assert_eq!(nsf.data, [0x60]);
+    }
+
+    #[test]
+    fn unknown_chunks() {
+        assert_eq!(
+            parse(&nsfe(&[(b"ZZZZ", &[1, 2, 3])])),
+            Err(NsfError::UnknownChunk(*b"ZZZZ"))
+        );
+        let nsf = parse(&nsfe(&[(b"zZZZ", &[1, 2, 3])])).unwrap();
+        assert_eq!(nsf.meta, parse(&nsfe(&[])).unwrap().meta);
+    }
+
+    #[test]
+    fn missing_chunks() {
+        let mut bytes = nsfe(&[]);
+        bytes.truncate(bytes.len() - 8);
+        assert_eq!(parse(&bytes), Err(NsfError::MissingChunk(*b"NEND")));
+
+        let mut bytes = MAGIC.to_vec();
+        chunk(&mut bytes, b"DATA", &[0x60]);
+        chunk(&mut bytes, b"INFO", &INFO);
+        chunk(&mut bytes, b"NEND", &[]);
+        assert_eq!(parse(&bytes), Err(NsfError::MissingChunk(*b"INFO")));
+
+        let mut bytes = MAGIC.to_vec();
+        chunk(&mut bytes, b"NEND", &[]);
+        assert_eq!(parse(&bytes), Err(NsfError::MissingChunk(*b"INFO")));
+
+        let mut bytes = MAGIC.to_vec();
+        chunk(&mut bytes, b"INFO", &INFO);
+        chunk(&mut bytes, b"NEND", &[]);
+        assert_eq!(parse(&bytes), Err(NsfError::MissingChunk(*b"DATA")));
+    }
+
+    #[test]
+    fn bad_chunks() {
+        assert_eq!(
+            parse(&nsfe(&[(b"INFO", &INFO)])),
+            Err(NsfError::BadChunk(*b"INFO"))
+        );
+        assert_eq!(
+            parse(&nsfe(&[(b"RATE", &[0x10])])),
+            Err(NsfError::BadChunk(*b"RATE"))
+        );
+        assert_eq!(
+            parse(&nsfe(&[(b"DATA", &[0x60])])),
+            Err(NsfError::BadChunk(*b"DATA"))
+        );
+        let bank = [0, 1, 2, 3, 4, 5, 6, 7];
+        assert_eq!(
+            parse(&nsfe(&[(b"BANK", &bank), (b"BANK", &bank)])),
+            Err(NsfError::BadChunk(*b"BANK"))
+        );
+        let nsf = parse(&nsfe(&[(b"BANK", &bank)])).unwrap();
+        assert_eq!(nsf.header.banks, bank);
+        let mut bytes = nsfe(&[]);
+        bytes.truncate(bytes.len() - 9);
+        assert_eq!(parse(&bytes), Err(NsfError::Truncated(bytes.len())));
+    }
+}
@@ -0,0 +1,462 @@
+//! NSF player
+//!
+//! [`Player`] runs the INIT and PLAY routines of an NSF on a [`Cpu`] with
+//! [`Cpu::call`]. [`Player::init`] starts a song and every [`Player::frame`]
+//! then runs one play period: PLAY is called and the cpu clock is advanced
+//! to the end of the period given by the play speed of the header.
+//!
+//! The NSF2 [`Features`] change the schedule. With a non-returning INIT, an
+//! INIT still running at the end of the first play period keeps running as
+//! the main loop of the tune and each frame interrupts it to call PLAY, the
+//! way an NMI would, restoring the registers of the loop afterwards. Once
+//! the loop returns after all, PLAY runs on its own. With PLAY suppressed
+//! only INIT runs. With IRQ support the bus is wrapped in an [`IrqVector`]
+//! so the tune can install its own IRQ handler and program the IRQ timer at
+//! $401B-$401D. The player steps the cpu itself and clocks the [`IrqLine`]
+//! of the bus after every instruction, also while it idles between PLAY
+//! calls, so the handler runs once the tune clears I.
+
+use crate::nsf::header::{Features, Header, Region, NTSC_SPEED, PAL_SPEED};
+use crate::op65::context::{AddressError, BusMut};
+use crate::op65::cpu::{CallError, Cpu, CALL_SENTINEL, IRQ_VECTOR};
+
+/// NTSC cpu clock in Hz
+pub const NTSC_CLOCK: u64 = 1_789_773;
+/// PAL cpu clock in Hz
+pub const PAL_CLOCK: u64 = 1_662_607;
+/// first NSF2 IRQ timer register. $401B and $401C hold the reload value,
+/// $401D is the control register.
+pub const IRQ_TIMER: u16 = 0x401B;
+/// NSF2 IRQ timer control register
+pub const IRQ_CONTROL: u16 = 0x401D;
+
+/// IrqLine is a bus with devices that raise IRQs as the cpu clock advances
+pub trait IrqLine {
+    /// advance the devices by cpu cycles and return whether the IRQ line is
+    /// active
+    fn clock(&mut self, cycles: u32) -> bool;
+}
+
+/// Player calls the INIT and PLAY routines of an NSF on its cpu
+pub struct Player {
+    cpu: Cpu,
+    init: u16,
+    play: u16,
+    features: Features,
+    pal: bool,
+    /// cpu clock in Hz
+    clock: u64,
+    /// play period in microseconds
+    speed: u64,
+    /// cycle the current frame ends on
+    deadline: u64,
+    /// remainder of the play period in cycles, in millionths
+    carry: u64,
+    /// INIT has not returned and runs as the main loop
+    looping: bool,
+    /// stack pointer INIT was called with, it is back there on return
+    init_sp: u8,
+}
+
+/// IrqVector makes the IRQ vector at $FFFE-$FFFF of a bus writable and adds
+/// the IRQ timer, as NSF2 IRQ support requires. A byte of the vector reads
+/// from the bus until it is written.
+///
+/// The timer counts cpu cycles down from the reload value written to
+/// $401B-$401C. Reaching zero raises the IRQ and reloads the counter. A
+/// write to $401D acknowledges the IRQ, with bit 0 set it reloads and starts
+/// the counter, with bit 0 clear it stops it. Reads of the registers go to
+/// the bus.
+pub struct IrqVector<B: BusMut> {
+    inner: B,
+    vector: [Option<u8>; 2],
+    /// timer reload value
+    reload: u16,
+    /// timer counter, running when set
+    counter: Option<u16>,
+    /// the timer raised the IRQ
+    active: bool,
+}
+
+impl Player {
+    /// create a player for the songs of a header. `pal` selects the system
+    /// a dual-region tune plays on, the other tunes play on their own.
+    pub fn new(header: &Header, pal: bool) -> Player {
+        let pal = match header.region {
+            Region::Ntsc => false,
+            Region::Pal => true,
+            Region::Dual => pal,
+        };
+        let (clock, speed) = match pal {
+            false => (NTSC_CLOCK, header.ntsc_speed),
+            true => (PAL_CLOCK, header.pal_speed),
+        };
+        let speed = match (speed, pal) {
+            (0, false) => NTSC_SPEED,
+            (0, true) => PAL_SPEED,
+            (speed, _) => speed,
+        };
+        Player {
+            cpu: Cpu::new(),
+            init: header.init,
+            play: header.play,
+            features: header.features,
+            pal,
+            clock,
+            speed: speed as u64,
+            deadline: 0,
+            carry: 0,
+            looping: false,
+            init_sp: 0,
+        }
+    }
+
+    /// cpu running the routines
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    /// cpu running the routines, for mutation
+    pub fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    /// check whether the tune plays at PAL speed
+    pub fn pal(&self) -> bool {
+        self.pal
+    }
+
+    /// check whether the bus needs an [`IrqVector`]
+    pub fn irq(&self) -> bool {
+        self.features.irq()
+    }
+
+    /// check whether INIT has not returned and runs as the main loop
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    /// call INIT for a song, counting from 0, and return the cycles it took.
+    /// An INIT still running after `budget` cycles times out, unless the
+    /// tune has a non-returning INIT: it is then cut off at the end of the
+    /// first play period, where the first PLAY comes, and left running
+    /// between the PLAY calls.
+    pub fn init<B: BusMut + IrqLine + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        song: u8,
+        budget: u64,
+    ) -> Result<u64, CallError> {
+        self.looping = false;
+        self.carry = 0;
+        let budget = match self.features.non_returning_init() {
+            true => budget.min(self.period()),
+            false => budget,
+        };
+        let region = self.pal as u8;
+        self.init_sp = self.cpu.reg.sp;
+        let res = self.call(bus, self.init, song, region, budget);
+        self.deadline = self.cpu.cycles;
+        match res {
+            Err(CallError::Timeout(cycles))
+                if self.features.non_returning_init() =>
+            {
+                self.looping = true;
+                Ok(cycles)
+            }
+            // the first period starts once INIT returns
+            res => {
+                self.carry = 0;
+                res
+            }
+        }
+    }
+
+    /// run one play period and return the cycles it took. PLAY must return
+    /// within `budget` cycles. A PLAY overrunning the period delays the
+    /// next one. A looping INIT that returns leaves PLAY to run on its own.
+    pub fn frame<B: BusMut + IrqLine + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        budget: u64,
+    ) -> Result<u64, CallError> {
+        let start = self.cpu.cycles;
+        self.deadline += self.period();
+        if !self.features.suppress_play() {
+            let saved = self.cpu.reg;
+            self.call(bus, self.play, saved.a, saved.x, budget)?;
+            if self.looping {
+                self.cpu.reg = saved;
+            }
+        }
+        while self.looping && self.cpu.cycles < self.deadline {
+            let reg = &self.cpu.reg;
+            if reg.pc == CALL_SENTINEL && reg.sp == self.init_sp {
+                self.looping = false;
+            } else {
+                self.step(bus)?;
+            }
+        }
+        // idle until the end of the period. An IRQ runs its handler, which
+        // returns to the idle cpu.
+        let sp = self.cpu.reg.sp;
+        while !self.looping && self.cpu.cycles < self.deadline {
+            if !self.features.irq() {
+                self.cpu.cycles = self.deadline;
+            } else if self.idle(sp) {
+                let irq = bus.clock(1);
+                self.cpu.set_irq(irq);
+                self.cpu.wait(1);
+            } else {
+                self.step(bus)?;
+            }
+        }
+        Ok(self.cpu.cycles - start)
+    }
+
+    /// check whether the cpu is back from a call made at the stack pointer
+    /// and has no interrupt to take
+    fn idle(&self, sp: u8) -> bool {
+        let reg = &self.cpu.reg;
+        reg.pc == CALL_SENTINEL && reg.sp == sp && !self.cpu.pending()
+    }
+
+    /// call a routine as [`Cpu::call`] does, clocking the IRQ line of the bus
+    fn call<B: BusMut + IrqLine + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        a: u8,
+        x: u8,
+        budget: u64,
+    ) -> Result<u64, CallError> {
+        let start = self.cpu.cycles;
+        let sp = self.cpu.reg.sp;
+        self.cpu.enter(bus, addr, a, x).map_err(CallError::Fault)?;
+        while self.cpu.reg.pc != CALL_SENTINEL || self.cpu.reg.sp != sp {
+            let used = self.cpu.cycles - start;
+            if used >= budget {
+                return Err(CallError::Timeout(used));
+            }
+            self.step(bus)?;
+        }
+        Ok(self.cpu.cycles - start)
+    }
+
+    /// execute one instruction and clock the IRQ line of the bus
+    fn step<B: BusMut + IrqLine + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u32, CallError> {
+        let cycles = self.cpu.step(bus).map_err(CallError::Fault)?;
+        let irq = bus.clock(cycles);
+        self.cpu.set_irq(irq);
+        Ok(cycles)
+    }
+
+    /// cycles of the next play period, the fractions add up over frames
+    fn period(&mut self) -> u64 {
+        let total = self.speed * self.clock + self.carry;
+        self.carry = total % 1_000_000;
+        total / 1_000_000
+    }
+}
+
+impl<B: BusMut> IrqVector<B> {
+    /// wrap a bus
+    pub fn new(inner: B) -> IrqVector<B> {
+        IrqVector {
+            inner,
+            vector: [None; 2],
+            reload: 0,
+            counter: None,
+            active: false,
+        }
+    }
+
+    /// forget the written vector, it reads from the bus again, and stop the
+    /// IRQ timer
+    pub fn clear(&mut self) {
+        self.vector = [None; 2];
+        self.reload = 0;
+        self.counter = None;
+        self.active = false;
+    }
+
+    /// wrapped bus
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// wrapped bus, for mutation
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// unwrap the bus
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+// BusMut for IrqVector
+impl<B: BusMut> BusMut for IrqVector<B> {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        match addr.checked_sub(IRQ_VECTOR) {
+            Some(i) => match self.vector[i as usize] {
+                Some(val) => Ok(val),
+                None => self.inner.get(addr),
+            },
+            None => self.inner.get(addr),
+        }
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        match addr {
+            IRQ_TIMER => self.reload = self.reload & 0xFF00 | val as u16,
+            0x401C => {
+                self.reload = self.reload & 0x00FF | (val as u16) << 8;
+            }
+            IRQ_CONTROL => {
+                self.active = false;
+                self.counter = (val & 0x01 != 0).then_some(self.reload);
+            }
+            IRQ_VECTOR..=0xFFFF => {
+                self.vector[(addr - IRQ_VECTOR) as usize] = Some(val);
+            }
+            _ => return self.inner.set(addr, val),
+        }
+        Ok(())
+    }
+}
+
+// IrqLine for IrqVector, driven by the IRQ timer
+impl<B: BusMut> IrqLine for IrqVector<B> {
+    fn clock(&mut self, cycles: u32) -> bool {
+        if let Some(counter) = self.counter.as_mut() {
+            for _ in 0..cycles {
+                match *counter {
+                    0 => {
+                        *counter = self.reload;
+                        self.active = true;
+                    }
+                    n => *counter = n - 1,
+                }
+            }
+        }
+        self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nsf::header::Chips;
+    use crate::op65::context::I;
+    use crate::op65::memmap::MemoryMap;
+
+    /// INIT at $8000 counts down X and Y, about eleven frames, and returns.
+    /// PLAY at $8100 counts its calls at $00.
+    fn tune(features: u8) -> (Player, IrqVector<MemoryMap>) {
+        let header = Header {
+            version: 2,
+            songs: 1,
+            start: 1,
+            load: 0x8000,
+            init: 0x8000,
+            play: 0x8100,
+            title: String::new(),
+            artist: String::new(),
+            copyright: String::new(),
+            ntsc_speed: NTSC_SPEED,
+            banks: [0; 8],
+            pal_speed: PAL_SPEED,
+            region: Region::Ntsc,
+            chips: Chips { chips: 0 },
+            features: Features { features },
+            data_len: 0,
+        };
+        let mut bus = MemoryMap::new().with_ram(0x0000, 0xFFFF, 0x10000);
+        let init = [
+            0xA2, 0x00, 0xA0, 0x00, 0x88, 0xD0, 0xFD, 0xCA, 0xD0, 0xFA, 0x60,
+        ];
+        bus.set_window(0x8000, &init).unwrap();
+        bus.set_window(0x8100, &[0xE6, 0x00, 0x60]).unwrap();
+        (Player::new(&header, false), IrqVector::new(bus))
+    }
+
+    #[test]
+    fn looping_init_returns() {
+        let (mut player, mut bus) = tune(0b0010_0000);
+        player.init(&mut bus, 0, NTSC_CLOCK).unwrap();
+        assert!(player.looping());
+        let mut frames = 0;
+        while player.looping() {
+            player.frame(&mut bus, NTSC_CLOCK).unwrap();
+            frames += 1;
+            assert!(frames < 20, "INIT never returned");
+        }
+        assert_eq!(bus.get(0x00), Ok(frames));
+        // PLAY alone, idling to the end of the period
+        let cycles = player.frame(&mut bus, NTSC_CLOCK).unwrap();
+        assert!((29780..=29781).contains(&cycles));
+        assert_eq!(bus.get(0x00), Ok(frames + 1));
+        assert!(!player.looping());
+    }
+
+    #[test]
+    fn looping_init_cut_off_at_first_play() {
+        let (mut player, mut bus) = tune(0b0010_0000);
+        let cycles = player.init(&mut bus, 0, NTSC_CLOCK).unwrap();
+        assert!(player.looping());
+        assert!((29780..29790).contains(&cycles));
+        assert_eq!(bus.get(0x00), Ok(0));
+        player.frame(&mut bus, NTSC_CLOCK).unwrap();
+        assert_eq!(bus.get(0x00), Ok(1));
+    }
+
+    #[test]
+    fn returning_init_times_out() {
+        let (mut player, mut bus) = tune(0);
+        let res = player.init(&mut bus, 0, 1000);
+        assert!(matches!(res, Err(CallError::Timeout(_))));
+        assert!(!player.looping());
+    }
+
+    #[test]
+    fn irq_timer() {
+        let (mut player, mut bus) = tune(0b0001_0000);
+        // INIT sets the handler and starts the timer at 1000 cycles
+        let init = [
+            0xA9, 0x00, 0x8D, 0xFE, 0xFF, // LDA #$00, STA $FFFE
+            0xA9, 0x82, 0x8D, 0xFF, 0xFF, // LDA #$82, STA $FFFF
+            0xA9, 0xE7, 0x8D, 0x1B, 0x40, // LDA #$E7, STA $401B
+            0xA9, 0x03, 0x8D, 0x1C, 0x40, // LDA #$03, STA $401C
+            0xA9, 0x01, 0x8D, 0x1D, 0x40, // LDA #$01, STA $401D
+            0x58, 0x60, // CLI, RTS
+        ];
+        bus.set_window(0x8000, &init).unwrap();
+        // the handler counts at $01 and acknowledges the IRQ
+        let handler = [0xE6, 0x01, 0x8D, 0x1D, 0x40, 0x40];
+        bus.set_window(0x8200, &handler).unwrap();
+        assert_eq!(bus.get(IRQ_VECTOR), Ok(0x00));
+        player.init(&mut bus, 0, NTSC_CLOCK).unwrap();
+        assert_eq!(bus.get_pointer(IRQ_VECTOR), Ok(0x8200));
+        assert_eq!(bus.inner_mut().get(IRQ_VECTOR + 1), Ok(0x00));
+        // a frame of 29780 cycles idles through about 29 periods
+        player.frame(&mut bus, NTSC_CLOCK).unwrap();
+        let count = bus.get(0x01).unwrap();
+        assert!((28..=30).contains(&count), "{} IRQs", count);
+        assert_eq!(bus.get(0x00), Ok(1));
+        // stopping the timer silences it
+        bus.set(IRQ_CONTROL, 0).unwrap();
+        player.frame(&mut bus, NTSC_CLOCK).unwrap();
+        assert_eq!(bus.get(0x01), Ok(count));
+        assert_eq!(bus.get(0x00), Ok(2));
+        // the IRQ is ignored while I is set
+        player.cpu_mut().reg.flags.set(I, true);
+        bus.set(IRQ_CONTROL, 1).unwrap();
+        player.frame(&mut bus, NTSC_CLOCK).unwrap();
+        assert_eq!(bus.get(0x01), Ok(count));
+    }
+}
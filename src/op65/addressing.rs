@@ -1,8 +1,132 @@
-use crate::op65::context::{AddressError, Bus, Registers};
+use crate::op65::context::{AddressError, BusMut, Registers, Variant};
 
+/// Addressing mode of an instruction, each one resolved by the operand
+/// struct of the same name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// [`OpAbs`] - $HHLL
+    Abs,
+    /// [`OpAbsX`] - $HHLL,X
+    AbsX,
+    /// [`OpAbsY`] - $HHLL,Y
+    AbsY,
+    /// Accumulator - A, resolved like [`OpImpl`]
+    Acc,
+    /// [`OpImmd`] - #$NN
+    Immd,
+    /// [`OpImpl`]
+    Impl,
+    /// [`OpInd`] - ($HHLL)
+    Ind,
+    /// [`OpIndX`] - ($LL,X)
+    IndX,
+    /// [`OpIndY`] - ($LL),Y
+    IndY,
+    /// [`OpRel`] - $NN
+    Rel,
+    /// [`OpZp`] - $LL
+    Zp,
+    /// [`OpZpX`] - $LL,X
+    ZpX,
+    /// [`OpZpY`] - $LL,Y
+    ZpY,
+    /// [`OpZpInd`] - ($LL), 65C02 only
+    ZpInd,
+    /// [`OpAbsXInd`] - ($HHLL,X), 65C02 only
+    AbsXInd,
+    /// [`OpZpRel`] - $LL,$NN, 65C02 only
+    ZpRel,
+}
+
+/// Resolved effective address of an operand
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resolved {
+    /// effective address
+    pub addr: u16,
+    /// indexing or branching crossed a page
+    pub crossed: bool,
+    /// address the cpu reads while it is still computing the effective
+    /// address. Indexed loads only read it when `crossed`, stores and
+    /// read-modify-write instructions always do.
+    pub dummy: Option<u16>,
+}
+
+/// Addressing resolves the effective address of an operand
 pub trait Addressing {
-    fn addr(&self, bus: &dyn Bus, reg: &Registers)
-        -> Result<u16, AddressError>;
+    /// resolve the effective address with its timing details on a cpu
+    /// variant. `reg.pc` is expected to point past the instruction, as it
+    /// does while the cpu executes it.
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError>;
+
+    /// resolve the effective address
+    fn addr<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<u16, AddressError> {
+        Ok(self.resolve(bus, reg, variant)?.addr)
+    }
+}
+
+impl Resolved {
+    /// an address reached without extra cycles
+    pub fn direct(addr: u16) -> Resolved {
+        Resolved {
+            addr,
+            crossed: false,
+            dummy: None,
+        }
+    }
+
+    /// add an index to a base address. The cpu first reads the address with
+    /// the uncorrected high byte, then fixes up a page crossing (+1 cycle).
+    pub fn indexed(base: u16, idx: u8) -> Resolved {
+        let addr = base.wrapping_add(idx as u16);
+        Resolved {
+            addr,
+            crossed: (base ^ addr) & 0xFF00 != 0,
+            dummy: Some((base & 0xFF00) | (addr & 0x00FF)),
+        }
+    }
+
+    /// branch target of a signed offset from `pc`. A taken branch costs +1
+    /// cycle and +1 more when the target is in another page.
+    pub fn relative(pc: u16, rel: u8) -> Resolved {
+        let addr = pc.wrapping_add(rel as i8 as u16);
+        let crossed = (pc ^ addr) & 0xFF00 != 0;
+        Resolved {
+            addr,
+            crossed,
+            dummy: crossed.then_some((pc & 0xFF00) | (addr & 0x00FF)),
+        }
+    }
+
+    /// zero page indexing wraps within the page, after a read of the base
+    pub fn zp_indexed(base: u8, idx: u8) -> Resolved {
+        Resolved {
+            addr: base.wrapping_add(idx) as u16,
+            crossed: false,
+            dummy: Some(base as u16),
+        }
+    }
+
+    /// the dummy read as a cpu variant performs it, the 65C02 reads the
+    /// last instruction byte at `pc - 1`
+    pub fn on(self, variant: Variant, pc: u16) -> Resolved {
+        match (variant, self.dummy) {
+            (Variant::Cmos65C02, Some(_)) => Resolved {
+                dummy: Some(pc.wrapping_sub(1)),
+                ..self
+            },
+            _ => self,
+        }
+    }
 }
 
 /// Absolute Addressing - $HHLL
@@ -62,3 +186,232 @@ pub struct OpZpX {
 pub struct OpZpY {
     pub addr: u8,
 }
+
+/// Zero Page Indirect Addressing - ($LL)
+pub struct OpZpInd {
+    pub ind: u8,
+}
+
+/// Absolute Indexed Indirect Addressing - ($HHLL,X)
+pub struct OpAbsXInd {
+    pub ind: u16,
+}
+
+/// Zero Page and Relative Addressing - $LL,$NN
+pub struct OpZpRel {
+    pub addr: u8,
+    pub rel: u8,
+}
+
+/// address of the high byte of the pointer at `ptr`. NMOS bug: the
+/// increment never carries, so the high byte of a pointer at $xxFF is read
+/// from $xx00. The 65C02 fixes it.
+pub fn pointer_high(ptr: u16, variant: Variant) -> u16 {
+    match variant {
+        Variant::Cmos65C02 => ptr.wrapping_add(1),
+        _ => (ptr & 0xFF00) | (ptr.wrapping_add(1) & 0x00FF),
+    }
+}
+
+// read a little-endian pointer from zero page, the high byte wraps to $00
+fn zp_pointer<B: BusMut + ?Sized>(
+    bus: &mut B,
+    ptr: u8,
+) -> Result<u16, AddressError> {
+    let lo = bus.get(ptr as u16)?;
+    let hi = bus.get(ptr.wrapping_add(1) as u16)?;
+    Ok(u16::from_le_bytes([lo, hi]))
+}
+
+impl Addressing for OpAbs {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::direct(self.addr))
+    }
+}
+
+impl Addressing for OpAbsX {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::indexed(self.addr, reg.x).on(variant, reg.pc))
+    }
+}
+
+impl Addressing for OpAbsY {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::indexed(self.addr, reg.y).on(variant, reg.pc))
+    }
+}
+
+// the value is part of the instruction, it has no address to resolve
+impl Addressing for OpImmd {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Err(AddressError::NoAddress)
+    }
+}
+
+impl Addressing for OpImpl {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Err(AddressError::NoAddress)
+    }
+}
+
+// the high byte is read as [`pointer_high`] says, the 65C02 spends a dummy
+// read on it
+impl Addressing for OpInd {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        let lo = bus.get(self.ind)?;
+        let hi = bus.get(pointer_high(self.ind, variant))?;
+        let res = Resolved::direct(u16::from_le_bytes([lo, hi]));
+        Ok(match variant {
+            Variant::Cmos65C02 => Resolved {
+                dummy: Some(reg.pc.wrapping_sub(1)),
+                ..res
+            },
+            _ => res,
+        })
+    }
+}
+
+impl Addressing for OpIndX {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        let ptr = Resolved::zp_indexed(self.ind, reg.x).on(variant, reg.pc);
+        Ok(Resolved {
+            addr: zp_pointer(bus, ptr.addr as u8)?,
+            ..ptr
+        })
+    }
+}
+
+impl Addressing for OpIndY {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        let base = zp_pointer(bus, self.ind)?;
+        Ok(Resolved::indexed(base, reg.y).on(variant, reg.pc))
+    }
+}
+
+// the offset is signed and relative to the next instruction
+impl Addressing for OpRel {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::relative(reg.pc, self.rel).on(variant, reg.pc))
+    }
+}
+
+impl Addressing for OpZp {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::direct(self.addr as u16))
+    }
+}
+
+impl Addressing for OpZpX {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::zp_indexed(self.addr, reg.x).on(variant, reg.pc))
+    }
+}
+
+impl Addressing for OpZpY {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::zp_indexed(self.addr, reg.y).on(variant, reg.pc))
+    }
+}
+
+impl Addressing for OpZpInd {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::direct(zp_pointer(bus, self.ind)?))
+    }
+}
+
+// the 65C02 reads the pointer without the NMOS page-wrap bug, after a
+// dummy read while it adds X
+impl Addressing for OpAbsXInd {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+        reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        let ptr = self.ind.wrapping_add(reg.x as u16);
+        let lo = bus.get(ptr)?;
+        let hi = bus.get(ptr.wrapping_add(1))?;
+        Ok(Resolved {
+            addr: u16::from_le_bytes([lo, hi]),
+            crossed: false,
+            dummy: Some(reg.pc.wrapping_sub(1)),
+        })
+    }
+}
+
+// resolves the tested byte, the branch target resolves like [`OpRel`]
+impl Addressing for OpZpRel {
+    fn resolve<B: BusMut + ?Sized>(
+        &self,
+        _bus: &mut B,
+        _reg: &Registers,
+        _variant: Variant,
+    ) -> Result<Resolved, AddressError> {
+        Ok(Resolved::direct(self.addr as u16))
+    }
+}
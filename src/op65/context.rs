@@ -1,6 +1,7 @@
 use std::result::Result;
 
 /// AddressError represents an error that can occur when accessing memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AddressError {
     /// indecated address is not available for access.
     Unavailable(u16),
@@ -8,13 +9,16 @@ pub enum AddressError {
     ReadOnly(u16),
     /// indecated address is write-only.
     WriteOnly(u16),
-    /// indecated device requested a halt.
+    /// indecated address halted the cpu (device request or KIL opcode).
     Halt(u16),
-    /// indecated address is out of bounds.
-    OutOfBounds,
+    /// a window starting at the indecated address runs past $FFFF.
+    OutOfBounds(u16),
+    /// operand has no effective address (immediate or implied).
+    NoAddress,
 }
 
 /// Flags represents the 6502 CPU flags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Flags {
     pub flags: u8,
 }
@@ -37,6 +41,7 @@ pub const O: Flags = Flags { flags: 0b0100_0000 };
 pub const N: Flags = Flags { flags: 0b1000_0000 };
 
 /// Registers represents the 6502 CPU registers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Registers {
     pub a: u8,        // accumulator
     pub x: u8,        // x index
@@ -46,6 +51,128 @@ pub struct Registers {
     pub flags: Flags, // processor status
 }
 
+/// Variant of the 6502 family executed by a cpu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// MOS 6502 with decimal mode, unofficial opcodes and NMOS quirks
+    Nmos6502,
+    /// Ricoh 2A03/2A07 of the NES: an NMOS 6502 without decimal mode
+    Ricoh2A03,
+    /// CMOS 65C02: decimal mode with valid N/Z flags, no unofficial opcodes,
+    /// fixed `JMP ($xxFF)`, D cleared by interrupts and no RMW double write
+    Cmos65C02,
+}
+
+/// base address of the stack page
+pub const STACK_PAGE: u16 = 0x0100;
+
+/// CallFrame is a likely JSR frame found on the stack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    /// stack address of the low byte of the pushed return address
+    pub addr: u16,
+    /// address of the JSR instruction
+    pub jsr: u16,
+    /// subroutine called by the JSR
+    pub target: u16,
+    /// address RTS returns to
+    pub ret: u16,
+}
+
+// Stack operations for Registers
+impl Registers {
+    /// address of the next free stack slot
+    pub fn stack_addr(&self) -> u16 {
+        STACK_PAGE | self.sp as u16
+    }
+
+    /// push a byte on the stack, SP wraps inside the stack page
+    pub fn push<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        val: u8,
+    ) -> Result<(), AddressError> {
+        bus.set(self.stack_addr(), val)?;
+        self.sp = self.sp.wrapping_sub(1);
+        Ok(())
+    }
+
+    /// pull a byte from the stack, SP wraps inside the stack page
+    pub fn pull<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u8, AddressError> {
+        self.sp = self.sp.wrapping_add(1);
+        bus.get(self.stack_addr())
+    }
+
+    /// push a word on the stack, high byte first as JSR does
+    pub fn push_word<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        val: u16,
+    ) -> Result<(), AddressError> {
+        self.push(bus, (val >> 8) as u8)?;
+        self.push(bus, val as u8)
+    }
+
+    /// pull a word from the stack, low byte first as RTS does
+    pub fn pull_word<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u16, AddressError> {
+        let lo = self.pull(bus)?;
+        let hi = self.pull(bus)?;
+        Ok(u16::from_le_bytes([lo, hi]))
+    }
+
+    /// bytes on the stack, from the top of the stack (SP + 1) to $01FF
+    pub fn stack<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Vec<u8>, AddressError> {
+        let top = self.stack_addr() + 1;
+        let mut buf = vec![0; (STACK_PAGE + 0x100 - top) as usize];
+        bus.read_window(top, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// likely JSR frames on the stack, innermost first. A word on the stack
+    /// is taken for a return address when the byte 2 below it is a JSR
+    /// opcode, so data pushed by the program may show up as a false frame.
+    /// The code is read through the bus, unreadable code is skipped.
+    pub fn call_frames<B: BusMut + ?Sized>(
+        &self,
+        bus: &mut B,
+    ) -> Result<Vec<CallFrame>, AddressError> {
+        let stack = self.stack(bus)?;
+        let top = self.stack_addr() + 1;
+        let mut frames = Vec::new();
+        let mut i = 0;
+        while i + 1 < stack.len() {
+            let pushed = u16::from_le_bytes([stack[i], stack[i + 1]]);
+            let jsr = pushed.wrapping_sub(2);
+            let target = match bus.get(jsr) {
+                Ok(0x20) => bus.get_pointer(jsr.wrapping_add(1)).ok(),
+                _ => None,
+            };
+            match target {
+                Some(target) => {
+                    frames.push(CallFrame {
+                        addr: top + i as u16,
+                        jsr,
+                        target,
+                        ret: pushed.wrapping_add(1),
+                    });
+                    i += 2;
+                }
+                None => i += 1,
+            }
+        }
+        Ok(frames)
+    }
+}
+
 /// Bus provides a way to access 6502 address space
 pub trait Bus {
     /// read a byte from the address space
@@ -65,6 +192,104 @@ pub trait Bus {
     fn set_window(&self, addr: u16, val: &[u8]) -> Result<(), AddressError>;
 }
 
+/// BusMut provides exclusive access to 6502 address space. Writes and reads
+/// (which may have side effects on devices) take `&mut self`, so
+/// implementations need no interior mutability, and windows are copied out,
+/// so they may span mirrored or banked regions. Only `get` and `set` are
+/// required. [`SharedBus`] adapts a [`Bus`].
+pub trait BusMut {
+    /// read a byte from the address space
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError>;
+
+    /// write a byte to the address space
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError>;
+
+    /// read a little-endian word from the address space, which be used for
+    /// reading 16-bit addresses
+    fn get_pointer(&mut self, addr: u16) -> Result<u16, AddressError> {
+        let lo = self.get(addr)?;
+        let hi = self.get(addr.wrapping_add(1))?;
+        Ok(u16::from_le_bytes([lo, hi]))
+    }
+
+    /// read a data-window of memory into a buffer. The window must not wrap
+    /// past $FFFF.
+    fn read_window(
+        &mut self,
+        addr: u16,
+        buf: &mut [u8],
+    ) -> Result<(), AddressError> {
+        if addr as usize + buf.len() > 0x10000 {
+            return Err(AddressError::OutOfBounds(addr));
+        }
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = self.get(addr + i as u16)?;
+        }
+        Ok(())
+    }
+
+    /// read a data-window of memory from the address space
+    fn get_window(
+        &mut self,
+        addr: u16,
+        size: u16,
+    ) -> Result<Vec<u8>, AddressError> {
+        let mut buf = vec![0; size as usize];
+        self.read_window(addr, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// write a data-window of memory to the address space. The window must
+    /// not wrap past $FFFF.
+    fn set_window(
+        &mut self,
+        addr: u16,
+        val: &[u8],
+    ) -> Result<(), AddressError> {
+        if addr as usize + val.len() > 0x10000 {
+            return Err(AddressError::OutOfBounds(addr));
+        }
+        for (i, v) in val.iter().enumerate() {
+            self.set(addr + i as u16, *v)?;
+        }
+        Ok(())
+    }
+}
+
+/// SharedBus adapts a shared [`Bus`] to [`BusMut`]
+pub struct SharedBus<'a, B: Bus + ?Sized>(pub &'a B);
+
+// BusMut for SharedBus
+impl<B: Bus + ?Sized> BusMut for SharedBus<'_, B> {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        self.0.get(addr)
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        self.0.set(addr, val)
+    }
+
+    fn get_pointer(&mut self, addr: u16) -> Result<u16, AddressError> {
+        self.0.get_pointer(addr)
+    }
+
+    fn get_window(
+        &mut self,
+        addr: u16,
+        size: u16,
+    ) -> Result<Vec<u8>, AddressError> {
+        Ok(self.0.get_window(addr, size)?.to_vec())
+    }
+
+    fn set_window(
+        &mut self,
+        addr: u16,
+        val: &[u8],
+    ) -> Result<(), AddressError> {
+        self.0.set_window(addr, val)
+    }
+}
+
 // Stringfy the AddressError
 impl std::fmt::Display for AddressError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -81,10 +306,141 @@ impl std::fmt::Display for AddressError {
             AddressError::Halt(addr) => {
                 write!(f, "<AddressError> Address halt: ${:04X}", addr)
             }
-            AddressError::OutOfBounds => {
-                write!(f, "<AddressError> Address out of bounds")
+            AddressError::OutOfBounds(addr) => {
+                write!(f, "<AddressError> Address out of bounds: ${:04X}", addr)
             }
+            AddressError::NoAddress => {
+                write!(f, "<AddressError> Operand has no address")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+/// Access is the kind of bus access the cpu was doing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// opcode or operand fetch at PC
+    Fetch,
+    /// data read, including pointers and vectors
+    Read,
+    /// data write
+    Write,
+    /// push or pull on the stack page
+    Stack,
+}
+
+/// Fault is an [`AddressError`] raised while the cpu was executing, with the
+/// context needed to track it down
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fault {
+    /// error returned by the bus
+    pub error: AddressError,
+    /// kind of the failed access
+    pub access: Access,
+    /// address of the faulting instruction
+    pub pc: u16,
+    /// opcode of the faulting instruction, `None` during interrupt and
+    /// reset sequences or when the opcode fetch itself failed
+    pub op: Option<u8>,
+    /// cpu cycle of the failed access
+    pub cycle: u64,
+}
+
+/// Action is what the cpu does about a bus error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// stop the instruction and return the fault
+    Abort,
+    /// jam the cpu until RESET and return the fault
+    Halt,
+    /// count the fault and go on: a failed read returns the last value on
+    /// the data bus (open bus) and a failed write is dropped
+    Ignore,
+}
+
+/// FaultPolicy selects the [`Action`] for each kind of [`AddressError`]
+/// raised by the bus while the cpu executes. Errors of dummy reads are
+/// always ignored and not counted. Strict mode, the default, aborts on
+/// everything; lenient mode lets badly ripped code keep running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FaultPolicy {
+    /// action for [`AddressError::Unavailable`]
+    pub unavailable: Action,
+    /// action for [`AddressError::ReadOnly`], such as writes to ROM
+    pub read_only: Action,
+    /// action for [`AddressError::WriteOnly`]
+    pub write_only: Action,
+    /// action for [`AddressError::Halt`] requested by a device
+    pub halt: Action,
+    /// action for any other error
+    pub other: Action,
+}
+
+impl FaultPolicy {
+    /// abort on every bus error, for debugging rips
+    pub const STRICT: FaultPolicy = FaultPolicy {
+        unavailable: Action::Abort,
+        read_only: Action::Abort,
+        write_only: Action::Abort,
+        halt: Action::Abort,
+        other: Action::Abort,
+    };
+
+    /// ignore unmapped and misdirected accesses, jam on device halts
+    pub const LENIENT: FaultPolicy = FaultPolicy {
+        unavailable: Action::Ignore,
+        read_only: Action::Ignore,
+        write_only: Action::Ignore,
+        halt: Action::Halt,
+        other: Action::Abort,
+    };
+
+    /// action for an error
+    pub fn action(&self, error: AddressError) -> Action {
+        match error {
+            AddressError::Unavailable(_) => self.unavailable,
+            AddressError::ReadOnly(_) => self.read_only,
+            AddressError::WriteOnly(_) => self.write_only,
+            AddressError::Halt(_) => self.halt,
+            _ => self.other,
+        }
+    }
+}
+
+impl Default for FaultPolicy {
+    fn default() -> FaultPolicy {
+        FaultPolicy::STRICT
+    }
+}
+
+// Stringfy the Access
+impl std::fmt::Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Access::Fetch => write!(f, "fetch"),
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+            Access::Stack => write!(f, "stack"),
+        }
+    }
+}
+
+// Stringfy the Fault
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<Fault> {} at PC ${:04X}", self.access, self.pc)?;
+        if let Some(op) = self.op {
+            write!(f, " (opcode ${:02X})", op)?;
         }
+        write!(f, ", cycle {}: {}", self.cycle, self.error)
+    }
+}
+
+impl std::error::Error for Fault {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
     }
 }
 
@@ -129,6 +485,51 @@ impl Flags {
     pub fn n(&self) -> bool {
         self.flags & 0b1000_0000 != 0
     }
+
+    /// check whether all of the given flags are set
+    pub fn contains(&self, flag: Flags) -> bool {
+        self.flags & flag.flags == flag.flags
+    }
+
+    /// set or clear the given flags
+    pub fn set(&mut self, flag: Flags, on: bool) {
+        if on {
+            self.flags |= flag.flags;
+        } else {
+            self.flags &= !flag.flags;
+        }
+    }
+
+    /// clear the given flags
+    pub fn clear(&mut self, flag: Flags) {
+        self.flags &= !flag.flags;
+    }
+
+    /// toggle the given flags
+    pub fn toggle(&mut self, flag: Flags) {
+        self.flags ^= flag.flags;
+    }
+
+    /// update N and Z from a result
+    pub fn set_nz(&mut self, val: u8) {
+        self.set(Z, val == 0);
+        self.set(N, val & 0x80 != 0);
+    }
+
+    /// the status byte pushed on the stack. U is always set, B only by PHP
+    /// and BRK (`brk`), not by IRQ and NMI.
+    pub fn to_stack(&self, brk: bool) -> u8 {
+        let b = if brk { B.flags } else { 0 };
+        self.flags | b | U.flags
+    }
+
+    /// the status loaded from a byte pulled by PLP or RTI. B and U do not
+    /// exist in the register: B reads as clear and U as set.
+    pub fn from_stack(val: u8) -> Flags {
+        Flags {
+            flags: (val & !B.flags) | U.flags,
+        }
+    }
 }
 
 // Bitwise AND for Flags
@@ -184,3 +585,252 @@ impl std::convert::From<Flags> for u8 {
         val.flags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// RAM from $0000 with only the required methods, unavailable past it
+    struct Flat(Vec<u8>);
+
+    impl BusMut for Flat {
+        fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+            let val = self.0.get(addr as usize);
+            val.copied().ok_or(AddressError::Unavailable(addr))
+        }
+
+        fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+            let byte = self.0.get_mut(addr as usize);
+            *byte.ok_or(AddressError::Unavailable(addr))? = val;
+            Ok(())
+        }
+    }
+
+    fn flat(size: usize) -> Flat {
+        Flat((0..size).map(|i| i as u8).collect())
+    }
+
+    #[test]
+    fn busmut_defaults() {
+        let mut bus = flat(0x10000);
+        assert_eq!(bus.get_pointer(0x1234).ok(), Some(0x3534));
+        // the pointer wraps around, windows must not
+        assert_eq!(bus.get_pointer(0xFFFF).ok(), Some(0x00FF));
+        let mut buf = [0; 2];
+        assert!(bus.read_window(0xFFFE, &mut buf).is_ok());
+        assert_eq!(buf, [0xFE, 0xFF]);
+        assert!(matches!(
+            bus.read_window(0xFFFF, &mut buf),
+            Err(AddressError::OutOfBounds(0xFFFF))
+        ));
+        assert_eq!(
+            bus.get_window(0xFFFC, 4).ok(),
+            Some(vec![0xFC, 0xFD, 0xFE, 0xFF])
+        );
+        assert!(matches!(
+            bus.get_window(0xFFFC, 5),
+            Err(AddressError::OutOfBounds(0xFFFC))
+        ));
+        assert!(bus.set_window(0xFFFF, &[0x42]).is_ok());
+        assert_eq!(bus.get(0xFFFF).ok(), Some(0x42));
+        // nothing is written by a window out of bounds
+        assert!(matches!(
+            bus.set_window(0xFFFF, &[1, 2]),
+            Err(AddressError::OutOfBounds(0xFFFF))
+        ));
+        assert_eq!((bus.0[0xFFFF], bus.0[0x0000]), (0x42, 0x00));
+    }
+
+    #[test]
+    fn busmut_errors() {
+        let mut bus = flat(0x8000);
+        assert!(matches!(
+            bus.get_pointer(0x7FFF),
+            Err(AddressError::Unavailable(0x8000))
+        ));
+        assert!(matches!(
+            bus.get_window(0x7FF0, 0x20),
+            Err(AddressError::Unavailable(0x8000))
+        ));
+        assert!(matches!(
+            bus.set_window(0x7FFF, &[1, 2]),
+            Err(AddressError::Unavailable(0x8000))
+        ));
+        // the window is written up to the failing address
+        assert_eq!(bus.0[0x7FFF], 1);
+    }
+
+    /// shared RAM that records the methods called
+    struct Legacy {
+        mem: Vec<u8>,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl Bus for Legacy {
+        fn get(&self, addr: u16) -> Result<u8, AddressError> {
+            self.calls.borrow_mut().push("get");
+            Ok(self.mem[addr as usize])
+        }
+
+        fn set(&self, _: u16, _: u8) -> Result<(), AddressError> {
+            self.calls.borrow_mut().push("set");
+            Ok(())
+        }
+
+        fn get_pointer(&self, addr: u16) -> Result<u16, AddressError> {
+            self.calls.borrow_mut().push("get_pointer");
+            let lo = self.mem[addr as usize];
+            Ok(u16::from_le_bytes([lo, self.mem[addr as usize + 1]]))
+        }
+
+        fn get_window(
+            &self,
+            addr: u16,
+            size: u16,
+        ) -> Result<&[u8], AddressError> {
+            self.calls.borrow_mut().push("get_window");
+            Ok(&self.mem[addr as usize..][..size as usize])
+        }
+
+        fn set_window(&self, _: u16, _: &[u8]) -> Result<(), AddressError> {
+            self.calls.borrow_mut().push("set_window");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shared_bus_delegates() {
+        let legacy = Legacy {
+            mem: (0..=0xFF).collect(),
+            calls: RefCell::new(Vec::new()),
+        };
+        let mut bus = SharedBus(&legacy);
+        assert_eq!(bus.get(0x10).ok(), Some(0x10));
+        assert!(bus.set(0x10, 0).is_ok());
+        assert_eq!(bus.get_pointer(0x10).ok(), Some(0x1110));
+        assert_eq!(bus.get_window(0x10, 3).ok(), Some(vec![0x10, 0x11, 0x12]));
+        assert!(bus.set_window(0x10, &[0; 3]).is_ok());
+        let calls = ["get", "set", "get_pointer", "get_window", "set_window"];
+        assert_eq!(*legacy.calls.borrow(), calls);
+    }
+
+    #[test]
+    fn policy_actions() {
+        use AddressError::*;
+        let errors = [
+            Unavailable(0),
+            ReadOnly(0),
+            WriteOnly(0),
+            Halt(0),
+            OutOfBounds(0),
+        ];
+        let strict = errors.map(|e| FaultPolicy::STRICT.action(e));
+        assert_eq!(strict, [Action::Abort; 5]);
+        let lenient = errors.map(|e| FaultPolicy::LENIENT.action(e));
+        let ignore = Action::Ignore;
+        assert_eq!(
+            lenient,
+            [ignore, ignore, ignore, Action::Halt, Action::Abort]
+        );
+        assert_eq!(FaultPolicy::default(), FaultPolicy::STRICT);
+    }
+
+    #[test]
+    fn flags_stack_byte() {
+        let flags = Flags { flags: 0b1100_0011 };
+        // U is always pushed, B only by PHP and BRK
+        assert_eq!(flags.to_stack(true), 0b1111_0011);
+        assert_eq!(flags.to_stack(false), 0b1110_0011);
+        // B is dropped and U set when the byte is pulled
+        assert_eq!(Flags::from_stack(0xFF).flags, 0b1110_1111);
+        assert_eq!(Flags::from_stack(0x00).flags, 0b0010_0000);
+        let pulled = Flags::from_stack(flags.to_stack(true));
+        assert!(pulled.u() && !pulled.b());
+    }
+
+    #[test]
+    fn flags_update() {
+        let mut flags = Flags { flags: 0 };
+        flags.set_nz(0x00);
+        assert!(flags.z() && !flags.n());
+        flags.set_nz(0x80);
+        assert!(!flags.z() && flags.n());
+        flags.set_nz(0x01);
+        assert!(!flags.z() && !flags.n());
+
+        flags.set(C | D, true);
+        assert!(flags.contains(C | D) && !flags.contains(C | Z));
+        flags.clear(C);
+        assert!(!flags.c() && flags.d());
+        flags.toggle(C | D);
+        assert!(flags.c() && !flags.d());
+        flags.set(C, false);
+        assert_eq!(flags.flags, 0);
+    }
+
+    #[test]
+    fn stack_wraps() {
+        let mut bus = Flat(vec![0; 0x10000]);
+        let mut reg = Registers {
+            a: 0,
+            x: 0,
+            y: 0,
+            sp: 0x00,
+            pc: 0,
+            flags: Flags { flags: 0 },
+        };
+        reg.push(&mut bus, 0x11).unwrap();
+        assert_eq!((reg.sp, bus.0[0x0100]), (0xFF, 0x11));
+        assert_eq!(reg.pull(&mut bus), Ok(0x11));
+        assert_eq!(reg.sp, 0x00);
+        // a word pushed at $0100 continues at $01FF
+        reg.push_word(&mut bus, 0x1234).unwrap();
+        assert_eq!((bus.0[0x0100], bus.0[0x01FF]), (0x12, 0x34));
+        assert_eq!(reg.sp, 0xFE);
+        assert_eq!(reg.pull_word(&mut bus), Ok(0x1234));
+        assert_eq!(reg.sp, 0x00);
+        // pulling from an empty stack wraps to the bottom of the page
+        reg.sp = 0xFF;
+        bus.0[0x0100] = 0x22;
+        assert_eq!(reg.pull(&mut bus), Ok(0x22));
+        assert_eq!(reg.sp, 0x00);
+    }
+
+    #[test]
+    fn call_frames() {
+        let mut bus = Flat(vec![0; 0x10000]);
+        // JSR $0300 at $0200, JSR $0400 at $0310
+        bus.set_window(0x0200, &[0x20, 0x00, 0x03]).unwrap();
+        bus.set_window(0x0310, &[0x20, 0x00, 0x04]).unwrap();
+        let mut reg = Registers {
+            a: 0,
+            x: 0,
+            y: 0,
+            sp: 0xFF,
+            pc: 0x0400,
+            flags: Flags { flags: 0 },
+        };
+        assert_eq!(reg.call_frames(&mut bus), Ok(vec![]));
+        reg.push_word(&mut bus, 0x0202).unwrap();
+        // a byte pushed by the subroutine is skipped
+        reg.push(&mut bus, 0x42).unwrap();
+        reg.push_word(&mut bus, 0x0312).unwrap();
+        assert_eq!(reg.stack(&mut bus), Ok(vec![0x12, 0x03, 0x42, 0x02, 0x02]));
+        let frames = [
+            CallFrame {
+                addr: 0x01FB,
+                jsr: 0x0310,
+                target: 0x0400,
+                ret: 0x0313,
+            },
+            CallFrame {
+                addr: 0x01FE,
+                jsr: 0x0200,
+                target: 0x0300,
+                ret: 0x0203,
+            },
+        ];
+        assert_eq!(reg.call_frames(&mut bus), Ok(frames.to_vec()));
+    }
+}
@@ -0,0 +1,2561 @@
+//! 6502 CPU core
+//!
+//! [`Cpu`] owns the [`Registers`] and executes one instruction per
+//! [`Cpu::step`] against any [`BusMut`]. Every cycle of the 6502 is a bus
+//! access, so the core performs the same reads and writes as the real chip
+//! (including dummy reads and the double write of read-modify-write
+//! instructions) and counts the cycles from them. A shared
+//! [`Bus`](crate::op65::context::Bus) is driven through
+//! [`SharedBus`](crate::op65::context::SharedBus). The bus is a generic
+//! parameter, so accesses to a concrete bus are statically dispatched and
+//! inlined, while `dyn BusMut` still works for buses chosen at run time.
+//!
+//! Interrupts are polled at the end of each instruction, as the chip does on
+//! its last cycle, and the polled interrupt sequence is run by the next
+//! [`Cpu::step`]. NMI is edge triggered through [`Cpu::nmi`] while IRQ is the
+//! level of [`Cpu::set_irq`].
+//!
+//! The [`Variant`] selects the chip: the Ricoh 2A03 of the NES (the
+//! default) ignores the decimal flag, a stock NMOS 6502 honors it and the
+//! CMOS 65C02 also fixes the NMOS quirks. The unofficial opcodes behave as on
+//! the NMOS chips, see <https://www.nesdev.org/wiki/CPU_unofficial_opcodes>.
+//! Decimal mode follows
+//! <http://www.6502.org/tutorials/decimal_mode.html#A>.
+//!
+//! [`Cpu::tick`] runs the cpu one clock at a time instead: each call performs
+//! the single bus access of that cycle, so devices with side effects see
+//! their reads and writes on the cycle the chip does them. The instruction in
+//! progress is re-executed up to the current cycle from the recorded bus
+//! values, so both modes share the same instruction code and can be mixed
+//! freely. [`Cpu::stall`] suspends the cpu for cycles stolen by DMA.
+//!
+//! Bus errors are handled by the [`FaultPolicy`] of the cpu: they abort the
+//! instruction with a [`Fault`], are counted and ignored, or jam the cpu.
+//! The KIL opcodes of the NMOS chips always jam it: [`Cpu::step`] returns
+//! the fault with [`AddressError::Halt`] and the address of the opcode, and
+//! keeps doing so without executing anything until [`Cpu::reset`].
+//! [`Cpu::jammed`] reports where the cpu stopped.
+//!
+//! [`Cpu::call`] runs a subroutine the way an NSF player calls INIT and
+//! PLAY: it returns the cycles taken once the RTS of the routine comes back,
+//! or gives up after a cycle budget so a routine that never returns cannot
+//! hang the player. [`Cpu::enter`] sets up the same call for a player that
+//! steps the cpu itself, to clock its devices in between.
+
+use crate::op65::addressing::{pointer_high, Mode, Resolved};
+use crate::op65::context::{Access, Action, AddressError, BusMut};
+use crate::op65::context::{Fault, FaultPolicy};
+use crate::op65::context::{Flags, Registers, Variant};
+use crate::op65::context::{C, D, I, N, O, U, Z};
+use crate::op65::opcodes::{cmos, Kind, OPCODES};
+
+/// "Magic" constant ORed into A by XAA ($8B). It depends on the chip and
+/// temperature on real hardware, $EE is the most commonly observed value.
+const XAA_MAGIC: u8 = 0xEE;
+
+/// "Magic" constant ORed into A by LXA ($AB). The 2A03 behaves as $FF, so
+/// LXA works as a plain immediate LAX.
+const LXA_MAGIC: u8 = 0xFF;
+
+/// NMI vector address
+pub const NMI_VECTOR: u16 = 0xFFFA;
+/// RESET vector address
+pub const RESET_VECTOR: u16 = 0xFFFC;
+/// IRQ and BRK vector address
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+/// address [`Cpu::call`] returns to. It is the high byte of the IRQ vector,
+/// which no code executes.
+pub const CALL_SENTINEL: u16 = 0xFFFF;
+
+/// CallError is the reason a [`Cpu::call`] did not return
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// the routine has not returned within the cycle budget. The cpu is left
+    /// inside the routine and can keep running, as a non-returning NSF2
+    /// INIT does.
+    Timeout(u64),
+    /// the routine faulted
+    Fault(Fault),
+}
+
+/// cpu state restored to re-execute the instruction in progress
+#[derive(Clone, Copy)]
+struct State {
+    reg: Registers,
+    decimal_cycle: bool,
+    nmi: bool,
+    pending: bool,
+    jam: Option<Fault>,
+}
+
+/// a bus cycle of the instruction in progress
+#[derive(Clone, Copy)]
+struct Cycle {
+    /// value read or written
+    value: u8,
+    /// an NMI edge arrived before the cycle
+    nmi: bool,
+}
+
+/// cycle-stepped progress of the current instruction
+///
+/// The instructions are straight-line code that performs all of their bus
+/// cycles, so [`Cpu::tick`] keeps no per-instruction state machine. Each
+/// tick restores the state saved before the instruction and runs it again
+/// from its first cycle: the cycles of the trace are replayed without
+/// touching the bus, the next cycle is performed and recorded, and the rest
+/// of the run is thrown away. An instruction of n cycles is run n times,
+/// which is cheap at 8 cycles or less, and keeps a single implementation of
+/// every instruction for both ways of stepping. NMI edges are recorded with
+/// the cycle they arrived on, so each re-run polls them at the same point.
+#[derive(Default)]
+struct Replay {
+    /// state before the instruction started
+    state: Option<State>,
+    /// bus cycles already performed
+    trace: Vec<Cycle>,
+    /// next bus cycle of the current run
+    cursor: usize,
+    /// the run stops using the bus after this cycle
+    stop: Option<usize>,
+    /// an NMI edge arrived since the last bus cycle
+    edge: bool,
+}
+
+/// Cpu represents a 6502 CPU core
+pub struct Cpu {
+    /// cpu registers
+    pub reg: Registers,
+    /// cycles executed since power on
+    pub cycles: u64,
+    /// chip variant
+    variant: Variant,
+    /// a 65C02 decimal ADC/SBC takes one more cycle
+    decimal_cycle: bool,
+    /// NMI edge latched and not yet serviced
+    nmi: bool,
+    /// level of the IRQ line
+    irq: bool,
+    /// an interrupt was polled and is serviced before the next instruction
+    pending: bool,
+    /// the cpu jammed on this fault and waits for RESET
+    jam: Option<Fault>,
+    /// cycles stolen by DMA and not yet spent
+    stall: u32,
+    /// address of the instruction in progress
+    op_pc: u16,
+    /// opcode of the instruction in progress
+    op: Option<u8>,
+    /// kind of the last bus access
+    access: Access,
+    /// last value on the data bus
+    data: u8,
+    /// handling of bus errors
+    policy: FaultPolicy,
+    /// bus errors ignored by the policy
+    suppressed: u64,
+    /// last bus error ignored by the policy
+    last_suppressed: Option<Fault>,
+    /// cycle-stepped progress of the current instruction
+    replay: Replay,
+}
+
+// Stringfy the CallError
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CallError::Timeout(cycles) => {
+                write!(f, "<CallError> No return after {} cycles", cycles)
+            }
+            CallError::Fault(fault) => write!(f, "<CallError> {}", fault),
+        }
+    }
+}
+
+impl std::error::Error for CallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CallError::Timeout(_) => None,
+            CallError::Fault(fault) => Some(fault),
+        }
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        Cpu::new()
+    }
+}
+
+impl Cpu {
+    /// create a 2A03 cpu in the power-on state
+    pub fn new() -> Cpu {
+        Cpu::with_variant(Variant::Ricoh2A03)
+    }
+
+    /// create a cpu of a variant in the power-on state
+    pub fn with_variant(variant: Variant) -> Cpu {
+        Cpu {
+            reg: Registers {
+                a: 0,
+                x: 0,
+                y: 0,
+                sp: 0xFD,
+                pc: 0,
+                flags: I | U,
+            },
+            cycles: 0,
+            variant,
+            decimal_cycle: false,
+            nmi: false,
+            irq: false,
+            pending: false,
+            jam: None,
+            stall: 0,
+            op_pc: 0,
+            op: None,
+            access: Access::Read,
+            data: 0,
+            policy: FaultPolicy::STRICT,
+            suppressed: 0,
+            last_suppressed: None,
+            replay: Replay::default(),
+        }
+    }
+
+    /// run the RESET sequence and return the cycles it consumed. Like the
+    /// real chip it performs the three stack pushes as reads, so SP drops by
+    /// 3, sets I and continues at the RESET vector. RESET also recovers a
+    /// jammed cpu.
+    pub fn reset<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u32, Fault> {
+        let start = self.cycles;
+        self.op_pc = self.reg.pc;
+        self.op = None;
+        self.replay = Replay::default();
+        self.jam = None;
+        self.nmi = false;
+        self.pending = false;
+        self.dummy_read(bus, self.reg.pc);
+        self.dummy_read(bus, self.reg.pc);
+        for _ in 0..3 {
+            self.dummy_read(bus, self.reg.stack_addr());
+            self.reg.sp = self.reg.sp.wrapping_sub(1);
+        }
+        self.reg.flags.set(I, true);
+        if self.variant == Variant::Cmos65C02 {
+            self.reg.flags.set(D, false);
+        }
+        self.reg.pc = self
+            .read_vector(bus, RESET_VECTOR)
+            .map_err(|e| self.fault(e))?;
+        Ok((self.cycles - start) as u32)
+    }
+
+    /// chip variant of the cpu
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// the fault that jammed the cpu, if any: a KIL opcode or a bus error
+    /// handled with [`Action::Halt`]
+    pub fn jammed(&self) -> Option<Fault> {
+        self.jam
+    }
+
+    /// handling of bus errors
+    pub fn policy(&self) -> FaultPolicy {
+        self.policy
+    }
+
+    /// select the handling of bus errors
+    pub fn set_policy(&mut self, policy: FaultPolicy) {
+        self.policy = policy;
+    }
+
+    /// bus errors ignored by the policy since the last
+    /// [`Cpu::clear_suppressed`]
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// the last bus error ignored by the policy
+    pub fn last_suppressed(&self) -> Option<Fault> {
+        self.last_suppressed
+    }
+
+    /// reset the count of ignored bus errors, such as when a track starts
+    pub fn clear_suppressed(&mut self) {
+        self.suppressed = 0;
+        self.last_suppressed = None;
+    }
+
+    /// signal a falling edge on the NMI line
+    pub fn nmi(&mut self) {
+        self.nmi = true;
+        self.replay.edge = true;
+    }
+
+    /// drive the IRQ line. IRQ is level triggered: it is serviced whenever
+    /// the line is active and I is clear, until the source releases it.
+    pub fn set_irq(&mut self, active: bool) {
+        self.irq = active;
+    }
+
+    /// execute one instruction at PC, or the interrupt sequence polled by the
+    /// previous instruction, and return the cycles it consumed. A jammed
+    /// cpu consumes no cycles and faults with [`AddressError::Halt`].
+    ///
+    /// An instruction partially run by [`Cpu::tick`] is completed and only
+    /// its remaining cycles are counted. Pending [`Cpu::stall`] cycles are
+    /// added to the result.
+    pub fn step<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u32, Fault> {
+        if let Some(jam) = self.jam {
+            return Err(jam);
+        }
+        let start = self.cycles;
+        self.cycles += std::mem::take(&mut self.stall) as u64;
+        if let Some(state) = self.replay.state {
+            self.restore(state);
+        }
+        self.replay.cursor = 0;
+        let res = self.run(bus);
+        self.replay.state = None;
+        self.replay.trace.clear();
+        res.map_err(|e| self.fault(e))?;
+        Ok((self.cycles - start) as u32)
+    }
+
+    /// advance the cpu by one clock cycle, performing the bus access of that
+    /// cycle. Returns true when the cycle completed an instruction or an
+    /// interrupt sequence. Stalled cycles are spent first, without using the
+    /// bus. A jammed cpu faults with [`AddressError::Halt`].
+    pub fn tick<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<bool, Fault> {
+        if let Some(jam) = self.jam {
+            return Err(jam);
+        }
+        if self.stall > 0 {
+            self.stall -= 1;
+            self.cycles += 1;
+            return Ok(false);
+        }
+        let state = match self.replay.state {
+            Some(state) => state,
+            None => self.save(),
+        };
+        self.restore(state);
+        self.replay.cursor = 0;
+        self.replay.stop = Some(self.replay.trace.len());
+        let res = self.run(bus);
+        self.replay.stop = None;
+        if self.replay.cursor > self.replay.trace.len() {
+            // the instruction continues on later cycles
+            self.restore(state);
+            self.replay.state = Some(state);
+            return Ok(false);
+        }
+        self.replay.state = None;
+        self.replay.trace.clear();
+        res.map(|_| true).map_err(|e| self.fault(e))
+    }
+
+    /// suspend the cpu for cycles stolen by DMA, such as the DMC sample
+    /// fetches of the 2A03. They are spent by the next [`Cpu::tick`] calls or
+    /// added to the next [`Cpu::step`].
+    pub fn stall(&mut self, cycles: u32) {
+        self.stall += cycles;
+    }
+
+    /// call the subroutine at `addr` with A and X set, as the NSF INIT and
+    /// PLAY routines are called, and run it until its RTS returns. Returns
+    /// the cycles used, or [`CallError::Timeout`] once `budget` cycles are
+    /// spent without returning.
+    ///
+    /// The return address of [`CALL_SENTINEL`] is pushed on the stack
+    /// without spending cycles, and the call is over when PC reaches it with
+    /// the stack balanced.
+    pub fn call<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        a: u8,
+        x: u8,
+        budget: u64,
+    ) -> Result<u64, CallError> {
+        let start = self.cycles;
+        let sp = self.reg.sp;
+        self.enter(bus, addr, a, x).map_err(CallError::Fault)?;
+        while self.reg.pc != CALL_SENTINEL || self.reg.sp != sp {
+            let used = self.cycles - start;
+            if used >= budget {
+                return Err(CallError::Timeout(used));
+            }
+            self.step(bus).map_err(CallError::Fault)?;
+        }
+        Ok(self.cycles - start)
+    }
+
+    /// set up the call of [`Cpu::call`] without running it: push the return
+    /// address of [`CALL_SENTINEL`], set A and X and jump to `addr`. The
+    /// caller steps the cpu until PC reaches the sentinel with the stack
+    /// back where it was.
+    pub fn enter<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        a: u8,
+        x: u8,
+    ) -> Result<(), Fault> {
+        if let Err(error) = self.reg.push_word(bus, CALL_SENTINEL - 1) {
+            return Err(Fault {
+                error,
+                access: Access::Stack,
+                pc: addr,
+                op: None,
+                cycle: self.cycles,
+            });
+        }
+        self.reg.a = a;
+        self.reg.x = x;
+        self.reg.pc = addr;
+        Ok(())
+    }
+
+    /// let the cpu idle for cycles without running code, as a player does
+    /// between calls. The interrupt lines are polled at the end, so an NMI
+    /// or an unmasked IRQ is taken by the next [`Cpu::step`].
+    pub fn wait(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+        self.pending = self.nmi || (self.irq && !self.reg.flags.i());
+    }
+
+    /// check whether the next [`Cpu::step`] runs an interrupt sequence
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    /// run the instruction at PC or the polled interrupt sequence
+    fn run<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<(), AddressError> {
+        self.op_pc = self.reg.pc;
+        self.op = None;
+        if self.pending {
+            self.pending = false;
+            self.dummy_read(bus, self.reg.pc);
+            self.dummy_read(bus, self.reg.pc);
+            self.interrupt(bus, false)?;
+        } else {
+            let masked = self.reg.flags.i();
+            let op = self.fetch(bus)?;
+            self.op = Some(op);
+            self.execute(bus, op)?;
+            // CLI, SEI and PLP change I after the cpu polled the IRQ line,
+            // so the new value only takes effect one instruction later
+            let masked = match op {
+                0x58 | 0x78 | 0x28 => masked,
+                _ => self.reg.flags.i(),
+            };
+            self.pending = self.nmi || (self.irq && !masked);
+        }
+        Ok(())
+    }
+
+    /// context of a bus error raised by the instruction in progress
+    fn fault(&self, error: AddressError) -> Fault {
+        Fault {
+            error,
+            access: self.access,
+            pc: self.op_pc,
+            op: self.op,
+            cycle: self.cycles,
+        }
+    }
+
+    fn save(&self) -> State {
+        State {
+            reg: self.reg,
+            decimal_cycle: self.decimal_cycle,
+            nmi: self.nmi,
+            pending: self.pending,
+            jam: self.jam,
+        }
+    }
+
+    fn restore(&mut self, state: State) {
+        self.reg = state.reg;
+        self.decimal_cycle = state.decimal_cycle;
+        self.nmi = state.nmi;
+        self.pending = state.pending;
+        self.jam = state.jam;
+    }
+
+    fn execute<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        op: u8,
+    ) -> Result<(), AddressError> {
+        // the table gives the addressing mode, the match the operation
+        let mode = match self.variant {
+            Variant::Cmos65C02 => cmos::OPCODES[op as usize].mode,
+            _ => OPCODES[op as usize].mode,
+        };
+        if self.variant == Variant::Cmos65C02
+            && OPCODES[op as usize].kind != Kind::Official
+        {
+            return self.execute_cmos(bus, op, mode);
+        }
+        match op {
+            // load and store
+            0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
+                self.load(bus, mode, Cpu::lda)?
+            }
+            0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => {
+                self.load(bus, mode, Cpu::ldx)?
+            }
+            0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => {
+                self.load(bus, mode, Cpu::ldy)?
+            }
+            0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
+                self.store(bus, mode, self.reg.a)?
+            }
+            0x86 | 0x96 | 0x8E => self.store(bus, mode, self.reg.x)?,
+            0x84 | 0x94 | 0x8C => self.store(bus, mode, self.reg.y)?,
+
+            // arithmetic and logic
+            0x69 | 0x65 | 0x75 | 0x6D | 0x7D | 0x79 | 0x61 | 0x71 => {
+                self.load(bus, mode, Cpu::adc)?
+            }
+            0xE9 | 0xE5 | 0xF5 | 0xED | 0xFD | 0xF9 | 0xE1 | 0xF1 => {
+                self.load(bus, mode, Cpu::sbc)?
+            }
+            0x29 | 0x25 | 0x35 | 0x2D | 0x3D | 0x39 | 0x21 | 0x31 => {
+                self.load(bus, mode, Cpu::and)?
+            }
+            0x09 | 0x05 | 0x15 | 0x0D | 0x1D | 0x19 | 0x01 | 0x11 => {
+                self.load(bus, mode, Cpu::ora)?
+            }
+            0x49 | 0x45 | 0x55 | 0x4D | 0x5D | 0x59 | 0x41 | 0x51 => {
+                self.load(bus, mode, Cpu::eor)?
+            }
+            0xC9 | 0xC5 | 0xD5 | 0xCD | 0xDD | 0xD9 | 0xC1 | 0xD1 => {
+                self.load(bus, mode, Cpu::cmp)?
+            }
+            0xE0 | 0xE4 | 0xEC => self.load(bus, mode, Cpu::cpx)?,
+            0xC0 | 0xC4 | 0xCC => self.load(bus, mode, Cpu::cpy)?,
+            0x24 | 0x2C => self.load(bus, mode, Cpu::bit)?,
+
+            // read-modify-write
+            0x0A => self.modify_a(bus, Cpu::asl)?,
+            0x06 | 0x16 | 0x0E => self.modify(bus, mode, Cpu::asl)?,
+            0x1E => self.shift_x(bus, Cpu::asl)?,
+            0x4A => self.modify_a(bus, Cpu::lsr)?,
+            0x46 | 0x56 | 0x4E => self.modify(bus, mode, Cpu::lsr)?,
+            0x5E => self.shift_x(bus, Cpu::lsr)?,
+            0x2A => self.modify_a(bus, Cpu::rol)?,
+            0x26 | 0x36 | 0x2E => self.modify(bus, mode, Cpu::rol)?,
+            0x3E => self.shift_x(bus, Cpu::rol)?,
+            0x6A => self.modify_a(bus, Cpu::ror)?,
+            0x66 | 0x76 | 0x6E => self.modify(bus, mode, Cpu::ror)?,
+            0x7E => self.shift_x(bus, Cpu::ror)?,
+            0xE6 | 0xF6 | 0xEE | 0xFE => self.modify(bus, mode, Cpu::inc)?,
+            0xC6 | 0xD6 | 0xCE | 0xDE => self.modify(bus, mode, Cpu::dec)?,
+
+            // register
+            0xE8 => self.implied(bus, |c| c.reg.x = c.inc(c.reg.x)),
+            0xC8 => self.implied(bus, |c| c.reg.y = c.inc(c.reg.y)),
+            0xCA => self.implied(bus, |c| c.reg.x = c.dec(c.reg.x)),
+            0x88 => self.implied(bus, |c| c.reg.y = c.dec(c.reg.y)),
+            0xAA => self.implied(bus, |c| c.reg.x = c.nz(c.reg.a)),
+            0xA8 => self.implied(bus, |c| c.reg.y = c.nz(c.reg.a)),
+            0x8A => self.implied(bus, |c| c.reg.a = c.nz(c.reg.x)),
+            0x98 => self.implied(bus, |c| c.reg.a = c.nz(c.reg.y)),
+            0xBA => self.implied(bus, |c| c.reg.x = c.nz(c.reg.sp)),
+            0x9A => self.implied(bus, |c| c.reg.sp = c.reg.x),
+            0xEA => self.implied(bus, |_| ()),
+
+            // status flags
+            0x18 => self.implied(bus, |c| c.reg.flags.set(C, false)),
+            0x38 => self.implied(bus, |c| c.reg.flags.set(C, true)),
+            0x58 => self.implied(bus, |c| c.reg.flags.set(I, false)),
+            0x78 => self.implied(bus, |c| c.reg.flags.set(I, true)),
+            0xB8 => self.implied(bus, |c| c.reg.flags.set(O, false)),
+            0xD8 => self.implied(bus, |c| c.reg.flags.set(D, false)),
+            0xF8 => self.implied(bus, |c| c.reg.flags.set(D, true)),
+
+            // branch
+            0x10 => self.branch(bus, !self.reg.flags.n())?,
+            0x30 => self.branch(bus, self.reg.flags.n())?,
+            0x50 => self.branch(bus, !self.reg.flags.v())?,
+            0x70 => self.branch(bus, self.reg.flags.v())?,
+            0x90 => self.branch(bus, !self.reg.flags.c())?,
+            0xB0 => self.branch(bus, self.reg.flags.c())?,
+            0xD0 => self.branch(bus, !self.reg.flags.z())?,
+            0xF0 => self.branch(bus, self.reg.flags.z())?,
+
+            // jump and subroutine
+            0x4C | 0x6C => self.reg.pc = self.operand(bus, mode, false)?,
+            0x20 => {
+                let lo = self.fetch(bus)?;
+                self.dummy_read(bus, self.reg.stack_addr());
+                self.push(bus, (self.reg.pc >> 8) as u8)?;
+                self.push(bus, self.reg.pc as u8)?;
+                let hi = self.fetch(bus)?;
+                self.reg.pc = u16::from_le_bytes([lo, hi]);
+            }
+            0x60 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let lo = self.pull(bus)?;
+                let hi = self.pull(bus)?;
+                self.reg.pc = u16::from_le_bytes([lo, hi]);
+                self.dummy_read(bus, self.reg.pc);
+                self.reg.pc = self.reg.pc.wrapping_add(1);
+            }
+            0x40 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let p = self.pull(bus)?;
+                self.reg.flags = Flags::from_stack(p);
+                let lo = self.pull(bus)?;
+                let hi = self.pull(bus)?;
+                self.reg.pc = u16::from_le_bytes([lo, hi]);
+            }
+            0x00 => {
+                self.fetch(bus)?;
+                self.interrupt(bus, true)?;
+            }
+
+            // stack
+            0x48 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.push(bus, self.reg.a)?;
+            }
+            0x08 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.push(bus, self.reg.flags.to_stack(true))?;
+            }
+            0x68 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let v = self.pull(bus)?;
+                self.reg.a = self.nz(v);
+            }
+            0x28 => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let p = self.pull(bus)?;
+                self.reg.flags = Flags::from_stack(p);
+            }
+
+            // unofficial: combined read-modify-write
+            0x07 | 0x17 | 0x0F | 0x1F | 0x1B | 0x03 | 0x13 => {
+                self.modify(bus, mode, Cpu::slo)?
+            }
+            0x27 | 0x37 | 0x2F | 0x3F | 0x3B | 0x23 | 0x33 => {
+                self.modify(bus, mode, Cpu::rla)?
+            }
+            0x47 | 0x57 | 0x4F | 0x5F | 0x5B | 0x43 | 0x53 => {
+                self.modify(bus, mode, Cpu::sre)?
+            }
+            0x67 | 0x77 | 0x6F | 0x7F | 0x7B | 0x63 | 0x73 => {
+                self.modify(bus, mode, Cpu::rra)?
+            }
+            0xC7 | 0xD7 | 0xCF | 0xDF | 0xDB | 0xC3 | 0xD3 => {
+                self.modify(bus, mode, Cpu::dcp)?
+            }
+            0xE7 | 0xF7 | 0xEF | 0xFF | 0xFB | 0xE3 | 0xF3 => {
+                self.modify(bus, mode, Cpu::isc)?
+            }
+
+            // unofficial: load and store
+            0xA7 | 0xB7 | 0xAF | 0xBF | 0xA3 | 0xB3 => {
+                self.load(bus, mode, Cpu::lax)?
+            }
+            0x87 | 0x97 | 0x8F | 0x83 => {
+                self.store(bus, mode, self.reg.a & self.reg.x)?
+            }
+            0xBB => self.load(bus, mode, Cpu::las)?,
+
+            // unofficial: immediate
+            0x0B | 0x2B => self.load(bus, mode, Cpu::anc)?,
+            0x4B => self.load(bus, mode, Cpu::alr)?,
+            0x6B => self.load(bus, mode, Cpu::arr)?,
+            0xCB => self.load(bus, mode, Cpu::axs)?,
+            0xEB => self.load(bus, mode, Cpu::sbc)?,
+            0x8B => self.load(bus, mode, Cpu::xaa)?,
+            0xAB => self.load(bus, mode, Cpu::lxa)?,
+
+            // unofficial: unstable stores that AND the value with the
+            // target high byte + 1
+            0x9F | 0x93 => {
+                self.store_high(bus, mode, self.reg.a & self.reg.x)?
+            }
+            0x9E => self.store_high(bus, mode, self.reg.x)?,
+            0x9C => self.store_high(bus, mode, self.reg.y)?,
+            0x9B => {
+                self.reg.sp = self.reg.a & self.reg.x;
+                self.store_high(bus, mode, self.reg.sp)?
+            }
+
+            // unofficial: no operation
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => {
+                self.implied(bus, |_| ())
+            }
+            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => {
+                self.load(bus, mode, Cpu::ign)?
+            }
+            0x04 | 0x44 | 0x64 => self.load(bus, mode, Cpu::ign)?,
+            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => {
+                self.load(bus, mode, Cpu::ign)?
+            }
+            0x0C => self.load(bus, mode, Cpu::ign)?,
+            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => {
+                self.load(bus, mode, Cpu::ign)?
+            }
+
+            // KIL
+            _ => return Err(self.kil(bus)),
+        }
+        Ok(())
+    }
+
+    /// 65C02 opcodes outside the NMOS official set: the new instructions,
+    /// the Rockwell bit instructions and the reserved NOPs
+    fn execute_cmos<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        op: u8,
+        mode: Mode,
+    ) -> Result<(), AddressError> {
+        match op {
+            // load and store
+            0xB2 => self.load(bus, mode, Cpu::lda)?,
+            0x92 => self.store(bus, mode, self.reg.a)?,
+            0x64 | 0x74 | 0x9C | 0x9E => self.store(bus, mode, 0)?,
+
+            // arithmetic and logic
+            0x72 => self.load(bus, mode, Cpu::adc)?,
+            0xF2 => self.load(bus, mode, Cpu::sbc)?,
+            0x32 => self.load(bus, mode, Cpu::and)?,
+            0x12 => self.load(bus, mode, Cpu::ora)?,
+            0x52 => self.load(bus, mode, Cpu::eor)?,
+            0xD2 => self.load(bus, mode, Cpu::cmp)?,
+            0x89 => self.load(bus, mode, Cpu::bit_imm)?,
+            0x34 | 0x3C => self.load(bus, mode, Cpu::bit)?,
+
+            // read-modify-write
+            0x1A => self.modify_a(bus, Cpu::inc)?,
+            0x3A => self.modify_a(bus, Cpu::dec)?,
+            0x14 | 0x1C => self.modify(bus, mode, Cpu::trb)?,
+            0x04 | 0x0C => self.modify(bus, mode, Cpu::tsb)?,
+
+            // bit instructions
+            _ if op & 0x0F == 0x07 => {
+                let mask = 1 << ((op >> 4) & 0x07);
+                let addr = self.operand(bus, mode, true)?;
+                let val = self.read(bus, addr)?;
+                self.dummy_read(bus, addr);
+                let val = if op & 0x80 != 0 {
+                    val | mask
+                } else {
+                    val & !mask
+                };
+                self.write(bus, addr, val)?;
+            }
+            _ if op & 0x0F == 0x0F => {
+                let mask = 1 << ((op >> 4) & 0x07);
+                let addr = self.operand(bus, mode, false)?;
+                let val = self.read(bus, addr)?;
+                self.dummy_read(bus, addr);
+                self.branch(bus, (val & mask != 0) == (op & 0x80 != 0))?;
+            }
+
+            // branch and jump
+            0x80 => self.branch(bus, true)?,
+            0x7C => self.reg.pc = self.operand(bus, mode, false)?,
+
+            // stack
+            0xDA => {
+                self.dummy_read(bus, self.reg.pc);
+                self.push(bus, self.reg.x)?;
+            }
+            0x5A => {
+                self.dummy_read(bus, self.reg.pc);
+                self.push(bus, self.reg.y)?;
+            }
+            0xFA => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let v = self.pull(bus)?;
+                self.reg.x = self.nz(v);
+            }
+            0x7A => {
+                self.dummy_read(bus, self.reg.pc);
+                self.dummy_read(bus, self.reg.stack_addr());
+                let v = self.pull(bus)?;
+                self.reg.y = self.nz(v);
+            }
+
+            // reserved opcodes are NOPs of different sizes and timing
+            _ if op & 0x07 == 0x03 => (),
+            0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 => {
+                self.load(bus, mode, Cpu::ign)?
+            }
+            0x44 | 0x54 | 0xD4 | 0xF4 | 0xDC | 0xFC => {
+                self.load(bus, mode, Cpu::ign)?
+            }
+            0x5C => {
+                let addr = self.fetch_word(bus)?;
+                for _ in 0..5 {
+                    self.dummy_read(bus, 0xFF00 | (addr & 0x00FF));
+                }
+            }
+            _ => unreachable!("${:02X} is not a 65C02 extension", op),
+        }
+        Ok(())
+    }
+
+    /// jam the cpu on a KIL opcode. The chip reads the next byte and then
+    /// stops; PC is left on the opcode so the state can be inspected.
+    fn kil<B: BusMut + ?Sized>(&mut self, bus: &mut B) -> AddressError {
+        self.dummy_read(bus, self.reg.pc);
+        let pc = self.reg.pc.wrapping_sub(1);
+        self.reg.pc = pc;
+        self.pending = false;
+        self.access = Access::Fetch;
+        self.jam = Some(self.fault(AddressError::Halt(pc)));
+        AddressError::Halt(pc)
+    }
+
+    // -- bus access: every call is one cpu cycle --
+
+    /// perform the bus cycle at the cursor. Cycles already performed by
+    /// [`Cpu::tick`] are replayed from the trace, cycles past the stop are
+    /// not run on the bus at all. `kind` is `None` for dummy reads.
+    fn access<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        kind: Option<Access>,
+        write: Option<u8>,
+    ) -> Result<u8, AddressError> {
+        let at = self.replay.cursor;
+        self.replay.cursor += 1;
+        if let Some(cycle) = self.replay.trace.get(at) {
+            self.nmi |= cycle.nmi;
+            return Ok(cycle.value);
+        }
+        if self.replay.stop.is_some_and(|stop| at > stop) {
+            return Ok(0);
+        }
+        let nmi = std::mem::take(&mut self.replay.edge);
+        self.nmi |= nmi;
+        self.cycles += 1;
+        self.access = kind.unwrap_or(Access::Read);
+        let res = match write {
+            Some(val) => bus.set(addr, val).map(|_| val),
+            None => bus.get(addr),
+        };
+        let res = match res {
+            Err(_) if kind.is_none() => Ok(self.data),
+            Err(e) => self.apply_policy(e, write),
+            res => res,
+        };
+        if let Ok(val) = res {
+            self.data = val;
+        }
+        if self.replay.stop.is_some() {
+            let value = *res.as_ref().unwrap_or(&0);
+            self.replay.trace.push(Cycle { value, nmi });
+        }
+        res
+    }
+
+    /// handle a bus error of a real access with the fault policy
+    fn apply_policy(
+        &mut self,
+        error: AddressError,
+        write: Option<u8>,
+    ) -> Result<u8, AddressError> {
+        match self.policy.action(error) {
+            Action::Abort => Err(error),
+            Action::Halt => {
+                self.jam = Some(self.fault(error));
+                Err(error)
+            }
+            Action::Ignore => {
+                self.suppressed += 1;
+                self.last_suppressed = Some(self.fault(error));
+                Ok(write.unwrap_or(self.data))
+            }
+        }
+    }
+
+    fn read<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+    ) -> Result<u8, AddressError> {
+        self.access(bus, addr, Some(Access::Read), None)
+    }
+
+    /// a read whose value is discarded by the cpu, so errors are ignored too
+    fn dummy_read<B: BusMut + ?Sized>(&mut self, bus: &mut B, addr: u16) {
+        let _ = self.access(bus, addr, None, None);
+    }
+
+    fn write<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        val: u8,
+    ) -> Result<(), AddressError> {
+        self.access(bus, addr, Some(Access::Write), Some(val))
+            .map(|_| ())
+    }
+
+    fn read_vector<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        vector: u16,
+    ) -> Result<u16, AddressError> {
+        let lo = self.read(bus, vector)?;
+        let hi = self.read(bus, vector + 1)?;
+        Ok(u16::from_le_bytes([lo, hi]))
+    }
+
+    fn fetch<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u8, AddressError> {
+        let val = self.access(bus, self.reg.pc, Some(Access::Fetch), None)?;
+        self.reg.pc = self.reg.pc.wrapping_add(1);
+        Ok(val)
+    }
+
+    fn fetch_word<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u16, AddressError> {
+        let lo = self.fetch(bus)?;
+        let hi = self.fetch(bus)?;
+        Ok(u16::from_le_bytes([lo, hi]))
+    }
+
+    fn push<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        val: u8,
+    ) -> Result<(), AddressError> {
+        let addr = self.reg.stack_addr();
+        self.access(bus, addr, Some(Access::Stack), Some(val))?;
+        self.reg.sp = self.reg.sp.wrapping_sub(1);
+        Ok(())
+    }
+
+    fn pull<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+    ) -> Result<u8, AddressError> {
+        self.reg.sp = self.reg.sp.wrapping_add(1);
+        self.access(bus, self.reg.stack_addr(), Some(Access::Stack), None)
+    }
+
+    /// push PC and status, then jump through the vector. B is only set in
+    /// the status pushed by BRK. An NMI arriving before the vector is read
+    /// hijacks the sequence of BRK and IRQ.
+    fn interrupt<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        brk: bool,
+    ) -> Result<(), AddressError> {
+        self.push(bus, (self.reg.pc >> 8) as u8)?;
+        self.push(bus, self.reg.pc as u8)?;
+        self.push(bus, self.reg.flags.to_stack(brk))?;
+        self.reg.flags.set(I, true);
+        if self.variant == Variant::Cmos65C02 {
+            self.reg.flags.set(D, false);
+        }
+        let vector = if self.nmi {
+            self.nmi = false;
+            NMI_VECTOR
+        } else {
+            IRQ_VECTOR
+        };
+        self.reg.pc = self.read_vector(bus, vector)?;
+        Ok(())
+    }
+
+    // -- addressing --
+
+    /// resolve the effective address of the operand. Stores and
+    /// read-modify-write instructions always spend the indexing cycle, loads
+    /// only when the index crosses a page.
+    fn operand<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        mode: Mode,
+        write: bool,
+    ) -> Result<u16, AddressError> {
+        match mode {
+            Mode::Immd => {
+                let addr = self.reg.pc;
+                self.reg.pc = self.reg.pc.wrapping_add(1);
+                Ok(addr)
+            }
+            Mode::Zp | Mode::ZpRel => Ok(self.fetch(bus)? as u16),
+            Mode::ZpX => {
+                let base = self.fetch(bus)?;
+                let res = Resolved::zp_indexed(base, self.reg.x);
+                Ok(self.fix_up(bus, res, true))
+            }
+            Mode::ZpY => {
+                let base = self.fetch(bus)?;
+                let res = Resolved::zp_indexed(base, self.reg.y);
+                Ok(self.fix_up(bus, res, true))
+            }
+            Mode::Abs => self.fetch_word(bus),
+            Mode::AbsX => {
+                let base = self.fetch_word(bus)?;
+                let res = Resolved::indexed(base, self.reg.x);
+                Ok(self.fix_up(bus, res, write))
+            }
+            Mode::AbsY => {
+                let base = self.fetch_word(bus)?;
+                let res = Resolved::indexed(base, self.reg.y);
+                Ok(self.fix_up(bus, res, write))
+            }
+            Mode::IndX => {
+                let ptr = self.fetch(bus)?;
+                let res = Resolved::zp_indexed(ptr, self.reg.x);
+                let ptr = self.fix_up(bus, res, true) as u8;
+                let lo = self.read(bus, ptr as u16)?;
+                let hi = self.read(bus, ptr.wrapping_add(1) as u16)?;
+                Ok(u16::from_le_bytes([lo, hi]))
+            }
+            Mode::IndY => {
+                let ptr = self.fetch(bus)?;
+                let lo = self.read(bus, ptr as u16)?;
+                let hi = self.read(bus, ptr.wrapping_add(1) as u16)?;
+                let base = u16::from_le_bytes([lo, hi]);
+                let res = Resolved::indexed(base, self.reg.y);
+                Ok(self.fix_up(bus, res, write))
+            }
+            Mode::Ind => {
+                let ptr = self.fetch_word(bus)?;
+                // the 65C02 spends one more cycle on the page-wrap fix
+                if self.variant == Variant::Cmos65C02 {
+                    self.dummy_read(bus, self.reg.pc.wrapping_sub(1));
+                }
+                let lo = self.read(bus, ptr)?;
+                let hi = self.read(bus, pointer_high(ptr, self.variant))?;
+                Ok(u16::from_le_bytes([lo, hi]))
+            }
+            Mode::ZpInd => {
+                let ptr = self.fetch(bus)?;
+                let lo = self.read(bus, ptr as u16)?;
+                let hi = self.read(bus, ptr.wrapping_add(1) as u16)?;
+                Ok(u16::from_le_bytes([lo, hi]))
+            }
+            Mode::AbsXInd => {
+                let base = self.fetch_word(bus)?;
+                self.dummy_read(bus, self.reg.pc.wrapping_sub(1));
+                let ptr = base.wrapping_add(self.reg.x as u16);
+                let lo = self.read(bus, ptr)?;
+                let hi = self.read(bus, ptr.wrapping_add(1))?;
+                Ok(u16::from_le_bytes([lo, hi]))
+            }
+            Mode::Acc | Mode::Impl | Mode::Rel => {
+                unreachable!("{:?} has no memory operand", mode)
+            }
+        }
+    }
+
+    /// spend the dummy read of a resolved address, which loads and branches
+    /// only pay on a page crossing. The 65C02 re-reads the last instruction
+    /// byte instead of the partial address.
+    fn fix_up<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        res: Resolved,
+        always: bool,
+    ) -> u16 {
+        let res = res.on(self.variant, self.reg.pc);
+        if let Some(dummy) = res.dummy {
+            if always || res.crossed {
+                self.dummy_read(bus, dummy);
+            }
+        }
+        res.addr
+    }
+
+    // -- instruction shapes --
+
+    fn load<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        mode: Mode,
+        op: fn(&mut Cpu, u8),
+    ) -> Result<(), AddressError> {
+        let addr = self.operand(bus, mode, false)?;
+        let val = self.read(bus, addr)?;
+        op(self, val);
+        if self.decimal_cycle {
+            self.decimal_cycle = false;
+            self.dummy_read(bus, addr);
+        }
+        Ok(())
+    }
+
+    fn store<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        mode: Mode,
+        val: u8,
+    ) -> Result<(), AddressError> {
+        let addr = self.operand(bus, mode, true)?;
+        self.write(bus, addr, val)
+    }
+
+    /// store for SHA/SHX/SHY/TAS. The value is ANDed with the high byte of
+    /// the base address + 1 and, when indexing crosses a page, the result
+    /// also replaces the high byte of the target address.
+    fn store_high<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        mode: Mode,
+        val: u8,
+    ) -> Result<(), AddressError> {
+        let addr = self.operand(bus, mode, true)?;
+        let idx = match mode {
+            Mode::AbsX => self.reg.x,
+            _ => self.reg.y,
+        };
+        let base = addr.wrapping_sub(idx as u16);
+        let val = val & ((base >> 8) as u8).wrapping_add(1);
+        let addr = if (base ^ addr) & 0xFF00 != 0 {
+            ((val as u16) << 8) | (addr & 0x00FF)
+        } else {
+            addr
+        };
+        self.write(bus, addr, val)
+    }
+
+    /// read-modify-write: the NMOS chips write the unmodified value back
+    /// first, the 65C02 reads it again
+    fn modify<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        mode: Mode,
+        op: fn(&mut Cpu, u8) -> u8,
+    ) -> Result<(), AddressError> {
+        let addr = self.operand(bus, mode, true)?;
+        self.modify_at(bus, addr, op)
+    }
+
+    fn modify_at<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        addr: u16,
+        op: fn(&mut Cpu, u8) -> u8,
+    ) -> Result<(), AddressError> {
+        let val = self.read(bus, addr)?;
+        if self.variant == Variant::Cmos65C02 {
+            self.dummy_read(bus, addr);
+        } else {
+            self.write(bus, addr, val)?;
+        }
+        let val = op(self, val);
+        self.write(bus, addr, val)
+    }
+
+    /// ASL/LSR/ROL/ROR $HHLL,X: the 65C02 skips the indexing cycle unless a
+    /// page is crossed
+    fn shift_x<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        op: fn(&mut Cpu, u8) -> u8,
+    ) -> Result<(), AddressError> {
+        let always = self.variant != Variant::Cmos65C02;
+        let addr = self.operand(bus, Mode::AbsX, always)?;
+        self.modify_at(bus, addr, op)
+    }
+
+    fn modify_a<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        op: fn(&mut Cpu, u8) -> u8,
+    ) -> Result<(), AddressError> {
+        self.dummy_read(bus, self.reg.pc);
+        self.reg.a = op(self, self.reg.a);
+        Ok(())
+    }
+
+    fn implied<B: BusMut + ?Sized>(&mut self, bus: &mut B, op: fn(&mut Cpu)) {
+        self.dummy_read(bus, self.reg.pc);
+        op(self);
+    }
+
+    fn branch<B: BusMut + ?Sized>(
+        &mut self,
+        bus: &mut B,
+        cond: bool,
+    ) -> Result<(), AddressError> {
+        let rel = self.fetch(bus)?;
+        if cond {
+            let res = Resolved::relative(self.reg.pc, rel);
+            self.dummy_read(bus, self.reg.pc);
+            self.reg.pc = self.fix_up(bus, res, false);
+        }
+        Ok(())
+    }
+
+    // -- status --
+
+    /// update N and Z from a result and pass it through
+    fn nz(&mut self, val: u8) -> u8 {
+        self.reg.flags.set_nz(val);
+        val
+    }
+
+    // -- operations --
+
+    fn lda(&mut self, val: u8) {
+        self.reg.a = self.nz(val);
+    }
+
+    fn ldx(&mut self, val: u8) {
+        self.reg.x = self.nz(val);
+    }
+
+    fn ldy(&mut self, val: u8) {
+        self.reg.y = self.nz(val);
+    }
+
+    /// decimal mode is in effect for ADC and SBC
+    fn decimal(&mut self) -> bool {
+        if !self.reg.flags.d() || self.variant == Variant::Ricoh2A03 {
+            return false;
+        }
+        self.decimal_cycle = self.variant == Variant::Cmos65C02;
+        true
+    }
+
+    fn adc(&mut self, val: u8) {
+        if self.decimal() {
+            return self.adc_decimal(val);
+        }
+        self.adc_binary(val);
+    }
+
+    fn adc_binary(&mut self, val: u8) {
+        let a = self.reg.a;
+        let sum = a as u16 + val as u16 + self.reg.flags.c() as u16;
+        let res = sum as u8;
+        self.reg.flags.set(C, sum > 0xFF);
+        self.reg.flags.set(O, (a ^ res) & (val ^ res) & 0x80 != 0);
+        self.reg.a = self.nz(res);
+    }
+
+    /// BCD addition. The NMOS chips take N and V from the intermediate
+    /// result after the low digit fix-up and Z from the binary sum, the
+    /// 65C02 takes N and Z from the result.
+    fn adc_decimal(&mut self, val: u8) {
+        let a = self.reg.a;
+        let c = self.reg.flags.c() as u16;
+        let bin = (a as u16 + val as u16 + c) as u8;
+        let mut lo = (a & 0x0F) as u16 + (val & 0x0F) as u16 + c;
+        if lo >= 0x0A {
+            lo = ((lo + 0x06) & 0x0F) + 0x10;
+        }
+        let mid =
+            (a & 0xF0) as i8 as i16 + (val & 0xF0) as i8 as i16 + lo as i16;
+        let mut res = (a & 0xF0) as u16 + (val & 0xF0) as u16 + lo;
+        if res >= 0xA0 {
+            res += 0x60;
+        }
+        self.reg.flags.set(O, !(-128..=127).contains(&mid));
+        self.reg.flags.set(C, res >= 0x100);
+        self.reg.a = res as u8;
+        if self.variant == Variant::Cmos65C02 {
+            self.nz(res as u8);
+        } else {
+            self.reg.flags.set(N, mid & 0x80 != 0);
+            self.reg.flags.set(Z, bin == 0);
+        }
+    }
+
+    fn sbc(&mut self, val: u8) {
+        if self.decimal() {
+            return self.sbc_decimal(val);
+        }
+        self.adc_binary(!val);
+    }
+
+    /// BCD subtraction. Flags are those of the binary subtraction, except
+    /// N and Z which the 65C02 takes from the result.
+    fn sbc_decimal(&mut self, val: u8) {
+        let a = self.reg.a;
+        let borrow = 1 - self.reg.flags.c() as i16;
+        let lo = (a & 0x0F) as i16 - (val & 0x0F) as i16 - borrow;
+        let res = if self.variant == Variant::Cmos65C02 {
+            let mut res = a as i16 - val as i16 - borrow;
+            if res < 0 {
+                res -= 0x60;
+            }
+            if lo < 0 {
+                res -= 0x06;
+            }
+            res
+        } else {
+            let lo = if lo < 0 {
+                ((lo - 0x06) & 0x0F) - 0x10
+            } else {
+                lo
+            };
+            let res = (a & 0xF0) as i16 - (val & 0xF0) as i16 + lo;
+            if res < 0 {
+                res - 0x60
+            } else {
+                res
+            }
+        };
+        self.adc_binary(!val);
+        self.reg.a = res as u8;
+        if self.variant == Variant::Cmos65C02 {
+            self.nz(res as u8);
+        }
+    }
+
+    fn and(&mut self, val: u8) {
+        self.reg.a = self.nz(self.reg.a & val);
+    }
+
+    fn ora(&mut self, val: u8) {
+        self.reg.a = self.nz(self.reg.a | val);
+    }
+
+    fn eor(&mut self, val: u8) {
+        self.reg.a = self.nz(self.reg.a ^ val);
+    }
+
+    fn compare(&mut self, reg: u8, val: u8) {
+        self.reg.flags.set(C, reg >= val);
+        self.nz(reg.wrapping_sub(val));
+    }
+
+    fn cmp(&mut self, val: u8) {
+        self.compare(self.reg.a, val);
+    }
+
+    fn cpx(&mut self, val: u8) {
+        self.compare(self.reg.x, val);
+    }
+
+    fn cpy(&mut self, val: u8) {
+        self.compare(self.reg.y, val);
+    }
+
+    fn bit(&mut self, val: u8) {
+        self.reg.flags.set(Z, self.reg.a & val == 0);
+        self.reg.flags.set(N, val & 0x80 != 0);
+        self.reg.flags.set(O, val & 0x40 != 0);
+    }
+
+    /// BIT #$NN only affects Z
+    fn bit_imm(&mut self, val: u8) {
+        self.reg.flags.set(Z, self.reg.a & val == 0);
+    }
+
+    /// test and reset bits: Z from A AND M, then clear the bits of A in M
+    fn trb(&mut self, val: u8) -> u8 {
+        self.reg.flags.set(Z, self.reg.a & val == 0);
+        val & !self.reg.a
+    }
+
+    /// test and set bits: Z from A AND M, then set the bits of A in M
+    fn tsb(&mut self, val: u8) -> u8 {
+        self.reg.flags.set(Z, self.reg.a & val == 0);
+        val | self.reg.a
+    }
+
+    fn asl(&mut self, val: u8) -> u8 {
+        self.reg.flags.set(C, val & 0x80 != 0);
+        self.nz(val << 1)
+    }
+
+    fn lsr(&mut self, val: u8) -> u8 {
+        self.reg.flags.set(C, val & 0x01 != 0);
+        self.nz(val >> 1)
+    }
+
+    fn rol(&mut self, val: u8) -> u8 {
+        let carry = self.reg.flags.c() as u8;
+        self.reg.flags.set(C, val & 0x80 != 0);
+        self.nz((val << 1) | carry)
+    }
+
+    fn ror(&mut self, val: u8) -> u8 {
+        let carry = (self.reg.flags.c() as u8) << 7;
+        self.reg.flags.set(C, val & 0x01 != 0);
+        self.nz((val >> 1) | carry)
+    }
+
+    fn inc(&mut self, val: u8) -> u8 {
+        self.nz(val.wrapping_add(1))
+    }
+
+    fn dec(&mut self, val: u8) -> u8 {
+        self.nz(val.wrapping_sub(1))
+    }
+
+    // -- unofficial operations --
+
+    fn slo(&mut self, val: u8) -> u8 {
+        let val = self.asl(val);
+        self.ora(val);
+        val
+    }
+
+    fn rla(&mut self, val: u8) -> u8 {
+        let val = self.rol(val);
+        self.and(val);
+        val
+    }
+
+    fn sre(&mut self, val: u8) -> u8 {
+        let val = self.lsr(val);
+        self.eor(val);
+        val
+    }
+
+    fn rra(&mut self, val: u8) -> u8 {
+        let val = self.ror(val);
+        self.adc(val);
+        val
+    }
+
+    fn dcp(&mut self, val: u8) -> u8 {
+        let val = val.wrapping_sub(1);
+        self.cmp(val);
+        val
+    }
+
+    fn isc(&mut self, val: u8) -> u8 {
+        let val = val.wrapping_add(1);
+        self.sbc(val);
+        val
+    }
+
+    fn lax(&mut self, val: u8) {
+        self.reg.a = val;
+        self.reg.x = self.nz(val);
+    }
+
+    fn las(&mut self, val: u8) {
+        let val = val & self.reg.sp;
+        self.reg.sp = val;
+        self.reg.a = val;
+        self.reg.x = self.nz(val);
+    }
+
+    fn anc(&mut self, val: u8) {
+        self.and(val);
+        self.reg.flags.set(C, self.reg.flags.n());
+    }
+
+    fn alr(&mut self, val: u8) {
+        self.and(val);
+        self.reg.a = self.lsr(self.reg.a);
+    }
+
+    /// AND + ROR, with C taken from bit 6 and V from bit 6 XOR bit 5. In
+    /// decimal mode the NMOS chips also apply a BCD fix-up to each digit.
+    fn arr(&mut self, val: u8) {
+        if self.decimal() {
+            return self.arr_decimal(val);
+        }
+        let carry = (self.reg.flags.c() as u8) << 7;
+        let res = self.nz(((self.reg.a & val) >> 1) | carry);
+        self.reg.flags.set(C, res & 0x40 != 0);
+        self.reg.flags.set(O, ((res >> 6) ^ (res >> 5)) & 0x01 != 0);
+        self.reg.a = res;
+    }
+
+    fn arr_decimal(&mut self, val: u8) {
+        let and = self.reg.a & val;
+        let carry = (self.reg.flags.c() as u8) << 7;
+        let mut res = (and >> 1) | carry;
+        self.reg.flags.set(N, carry != 0);
+        self.reg.flags.set(Z, res == 0);
+        self.reg.flags.set(O, (and ^ res) & 0x40 != 0);
+        if (and & 0x0F) + (and & 0x01) > 0x05 {
+            res = (res & 0xF0) | (res.wrapping_add(0x06) & 0x0F);
+        }
+        let hi_fix = (and >> 4) + ((and >> 4) & 0x01) > 0x05;
+        self.reg.flags.set(C, hi_fix);
+        if hi_fix {
+            res = res.wrapping_add(0x60);
+        }
+        self.reg.a = res;
+    }
+
+    /// X = (A & X) - imm, flags as CMP without borrow
+    fn axs(&mut self, val: u8) {
+        let ax = self.reg.a & self.reg.x;
+        self.reg.flags.set(C, ax >= val);
+        self.reg.x = self.nz(ax.wrapping_sub(val));
+    }
+
+    fn xaa(&mut self, val: u8) {
+        self.reg.a = self.nz((self.reg.a | XAA_MAGIC) & self.reg.x & val);
+    }
+
+    fn lxa(&mut self, val: u8) {
+        self.lax((self.reg.a | LXA_MAGIC) & val);
+    }
+
+    /// NOPs that still perform their operand read
+    fn ign(&mut self, _val: u8) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op65::addressing::*;
+    use crate::op65::context::{Bus, SharedBus};
+    use crate::op65::memmap::MemoryMap;
+    use crate::op65::opcodes::Kind;
+    use std::cell::RefCell;
+
+    /// flat 64 KiB RAM, driven through a [`SharedBus`]
+    struct Ram(RefCell<Vec<u8>>);
+
+    impl Bus for Ram {
+        fn get(&self, addr: u16) -> Result<u8, AddressError> {
+            Ok(self.0.borrow()[addr as usize])
+        }
+
+        fn set(&self, addr: u16, val: u8) -> Result<(), AddressError> {
+            self.0.borrow_mut()[addr as usize] = val;
+            Ok(())
+        }
+
+        fn get_pointer(&self, addr: u16) -> Result<u16, AddressError> {
+            let lo = self.get(addr)?;
+            let hi = self.get(addr.wrapping_add(1))?;
+            Ok(u16::from_le_bytes([lo, hi]))
+        }
+
+        /// the RAM cannot be borrowed out of its cell
+        fn get_window(&self, addr: u16, _: u16) -> Result<&[u8], AddressError> {
+            Err(AddressError::Unavailable(addr))
+        }
+
+        fn set_window(
+            &self,
+            addr: u16,
+            val: &[u8],
+        ) -> Result<(), AddressError> {
+            let start = addr as usize;
+            self.0.borrow_mut()[start..start + val.len()].copy_from_slice(val);
+            Ok(())
+        }
+    }
+
+    /// cpu of a variant with a flat 64 KiB RAM holding code at $0200
+    fn setup(variant: Variant, code: &[u8]) -> (Cpu, Ram) {
+        let mut mem = vec![0; 0x10000];
+        mem[0x0200..0x0200 + code.len()].copy_from_slice(code);
+        let mut cpu = Cpu::with_variant(variant);
+        cpu.reg.pc = 0x0200;
+        (cpu, Ram(RefCell::new(mem)))
+    }
+
+    /// execute one instruction, panicking on a bus error
+    fn step(cpu: &mut Cpu, bus: &dyn Bus) -> u32 {
+        cpu.step(&mut SharedBus(bus))
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// advance one cycle, panicking on a bus error
+    fn tick(cpu: &mut Cpu, bus: &dyn Bus) -> bool {
+        cpu.tick(&mut SharedBus(bus))
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// run the instruction at $0200 with A and P set, return the cycles
+    fn exec(variant: Variant, code: &[u8], a: u8, p: u8) -> (Cpu, u32) {
+        let (mut cpu, bus) = setup(variant, code);
+        cpu.reg.a = a;
+        cpu.reg.flags = Flags { flags: p };
+        let cycles = step(&mut cpu, &bus);
+        (cpu, cycles)
+    }
+
+    /// A with the N, V, Z and C flags
+    fn nvzc(cpu: &Cpu) -> (u8, bool, bool, bool, bool) {
+        let flags = &cpu.reg.flags;
+        (cpu.reg.a, flags.n(), flags.v(), flags.z(), flags.c())
+    }
+
+    const SEC: u8 = 0x01;
+    const SED: u8 = 0x08;
+
+    #[test]
+    fn adc_sbc_binary() {
+        let cases = [
+            // ADC
+            (0x69, 0x50, 0x50, 0, (0xA0, true, true, false, false)),
+            (0x69, 0xFF, 0x01, 0, (0x00, false, false, true, true)),
+            (0x69, 0x80, 0xFF, 0, (0x7F, false, true, false, true)),
+            (0x69, 0x01, 0x01, SEC, (0x03, false, false, false, false)),
+            // SBC
+            (0xE9, 0x50, 0xF0, SEC, (0x60, false, false, false, false)),
+            (0xE9, 0x50, 0xB0, SEC, (0xA0, true, true, false, false)),
+            (0xE9, 0x00, 0x01, SEC, (0xFF, true, false, false, false)),
+            (0xE9, 0x05, 0x04, 0, (0x00, false, false, true, true)),
+        ];
+        for variant in [Variant::Nmos6502, Variant::Cmos65C02] {
+            for (op, a, val, p, want) in cases {
+                let (cpu, _) = exec(variant, &[op, val], a, p);
+                assert_eq!(
+                    nvzc(&cpu),
+                    want,
+                    "${:02X} {:02X} {:02X}",
+                    op,
+                    a,
+                    val
+                );
+            }
+        }
+        // the 2A03 ignores D
+        let (cpu, _) = exec(Variant::Ricoh2A03, &[0x69, 0x01], 0x09, SED);
+        assert_eq!(cpu.reg.a, 0x0A);
+    }
+
+    #[test]
+    fn adc_sbc_decimal() {
+        let cases = [
+            (0x69, 0x79, 0x00, SEC, (0x80, true, true, false, false)),
+            (0x69, 0x12, 0x34, 0, (0x46, false, false, false, false)),
+            (0xE9, 0x00, 0x01, SEC, (0x99, true, false, false, false)),
+            (0xE9, 0x46, 0x12, SEC, (0x34, false, false, false, true)),
+            (0xE9, 0x40, 0x13, 0, (0x26, false, false, false, true)),
+        ];
+        // NMOS: N from the intermediate result, Z from the binary sum. The
+        // 65C02 takes both from the result and spends one more cycle.
+        for (variant, nz, cycles) in [
+            (Variant::Nmos6502, (true, false), 2),
+            (Variant::Cmos65C02, (false, true), 3),
+        ] {
+            let (n, z) = nz;
+            let wrap = (0x69, 0x99, 0x01, 0, (0x00, n, false, z, true));
+            for (op, a, val, p, want) in [&[wrap][..], &cases].concat() {
+                let (cpu, used) = exec(variant, &[op, val], a, p | SED);
+                let case =
+                    format!("{:?} ${:02X} {:02X} {:02X}", variant, op, a, val);
+                assert_eq!(nvzc(&cpu), want, "{}", case);
+                assert_eq!(used, cycles, "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn arr() {
+        // AND + ROR, C from bit 6, V from bit 6 XOR bit 5
+        let (cpu, _) = exec(Variant::Nmos6502, &[0x6B, 0x80], 0xFF, 0);
+        assert_eq!(nvzc(&cpu), (0x40, false, true, false, true));
+        let (cpu, _) = exec(Variant::Nmos6502, &[0x6B, 0xC0], 0xFF, SEC);
+        assert_eq!(nvzc(&cpu), (0xE0, true, false, false, true));
+        let (cpu, _) = exec(Variant::Nmos6502, &[0x6B, 0x01], 0xFF, 0);
+        assert_eq!(nvzc(&cpu), (0x00, false, false, true, false));
+        // without decimal mode the 2A03 stays binary
+        let (cpu, _) = exec(Variant::Ricoh2A03, &[0x6B, 0x80], 0xFF, SED);
+        assert_eq!(nvzc(&cpu), (0x40, false, true, false, true));
+    }
+
+    #[test]
+    fn axs() {
+        let (mut cpu, bus) = setup(Variant::Nmos6502, &[0xCB, 0x10]);
+        cpu.reg.a = 0xF0;
+        cpu.reg.x = 0x3C;
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.x, 0x20);
+        assert!(cpu.reg.flags.c() && !cpu.reg.flags.n());
+        let (mut cpu, bus) = setup(Variant::Nmos6502, &[0xCB, 0x40]);
+        cpu.reg.a = 0xF0;
+        cpu.reg.x = 0x3C;
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.x, 0xF0);
+        assert!(!cpu.reg.flags.c() && cpu.reg.flags.n());
+        // A is left alone
+        assert_eq!(cpu.reg.a, 0xF0);
+    }
+
+    #[test]
+    fn xaa_lxa() {
+        // XAA: A = (A | $EE) & X & imm
+        let (mut cpu, bus) = setup(Variant::Nmos6502, &[0x8B, 0xFF]);
+        cpu.reg.a = 0x01;
+        cpu.reg.x = 0xF3;
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.a, 0xE3);
+        assert!(cpu.reg.flags.n());
+        // LXA: A = X = (A | $FF) & imm
+        let (mut cpu, bus) = setup(Variant::Nmos6502, &[0xAB, 0x5A]);
+        cpu.reg.a = 0x00;
+        step(&mut cpu, &bus);
+        assert_eq!((cpu.reg.a, cpu.reg.x), (0x5A, 0x5A));
+        assert!(!cpu.reg.flags.n() && !cpu.reg.flags.z());
+    }
+
+    #[test]
+    fn sha() {
+        // SHA $10F0,Y and SHA ($F0),Y with the pointer $10F0
+        for code in [[0x9F, 0xF0, 0x10], [0x93, 0xF0, 0x00]] {
+            // A & X & (high byte of the base + 1)
+            let (mut cpu, bus) = setup(Variant::Nmos6502, &code);
+            bus.0.borrow_mut()[0x00F0..0x00F2].copy_from_slice(&[0xF0, 0x10]);
+            cpu.reg.a = 0xFF;
+            cpu.reg.x = 0x3F;
+            cpu.reg.y = 0x01;
+            step(&mut cpu, &bus);
+            assert_eq!(bus.0.borrow()[0x10F1], 0x11);
+            // a page crossing also replaces the high byte of the target
+            let (mut cpu, bus) = setup(Variant::Nmos6502, &code);
+            bus.0.borrow_mut()[0x00F0..0x00F2].copy_from_slice(&[0xF0, 0x10]);
+            cpu.reg.a = 0xFF;
+            cpu.reg.x = 0x0F;
+            cpu.reg.y = 0x10;
+            step(&mut cpu, &bus);
+            assert_eq!(bus.0.borrow()[0x0100], 0x01);
+            assert_eq!(bus.0.borrow()[0x1100], 0x00);
+        }
+    }
+
+    #[test]
+    fn cycles_match_table() {
+        let variants = [
+            (Variant::Nmos6502, &OPCODES),
+            (Variant::Ricoh2A03, &OPCODES),
+            (Variant::Cmos65C02, &cmos::OPCODES),
+        ];
+        for (variant, table) in variants {
+            for code in table.iter() {
+                if code.kind == Kind::Jam
+                    || matches!(code.mode, Mode::Rel | Mode::ZpRel)
+                {
+                    continue;
+                }
+                // operand $10F0, and pointer $10F0 at $F0
+                for index in [0x00, 0xFF] {
+                    let (mut cpu, bus) = setup(variant, &[code.op, 0xF0, 0x10]);
+                    bus.0.borrow_mut()[0x00F0..0x00F2]
+                        .copy_from_slice(&[0xF0, 0x10]);
+                    cpu.reg.x = index;
+                    cpu.reg.y = index;
+                    let crossed = index == 0xFF
+                        && matches!(
+                            code.mode,
+                            Mode::AbsX | Mode::AbsY | Mode::IndY
+                        );
+                    let cycles =
+                        code.cyc as u32 + (code.penalty && crossed) as u32;
+                    assert_eq!(
+                        step(&mut cpu, &bus),
+                        cycles,
+                        "{:?} ${:02X} {} with index ${:02X}",
+                        variant,
+                        code.op,
+                        code.name,
+                        index
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cmos_extensions() {
+        // $3C at $10, the pointer $023C at $10 and $80 at $023C, A = $0F.
+        // Code, then $10, $023C, A and Z afterwards and the cycles.
+        let cases = [
+            (&[0x04, 0x10][..], 0x3F, 0x80, 0x0F, false, 5), // TSB $10
+            (&[0x0C, 0x10, 0x00], 0x3F, 0x80, 0x0F, false, 6), // TSB $0010
+            (&[0x14, 0x10], 0x30, 0x80, 0x0F, false, 5),     // TRB $10
+            (&[0x1C, 0x10, 0x00], 0x30, 0x80, 0x0F, false, 6), // TRB $0010
+            (&[0x64, 0x10], 0x00, 0x80, 0x0F, false, 3),     // STZ $10
+            (&[0x9C, 0x10, 0x00], 0x00, 0x80, 0x0F, false, 4), // STZ $0010
+            (&[0x27, 0x10], 0x38, 0x80, 0x0F, false, 5),     // RMB2 $10
+            (&[0x87, 0x10], 0x3D, 0x80, 0x0F, false, 5),     // SMB0 $10
+            (&[0xB2, 0x10], 0x3C, 0x80, 0x80, false, 5),     // LDA ($10)
+            (&[0x92, 0x10], 0x3C, 0x0F, 0x0F, false, 5),     // STA ($10)
+            (&[0x32, 0x10], 0x3C, 0x80, 0x00, true, 5),      // AND ($10)
+        ];
+        for (code, m, ind, a, z, cycles) in cases {
+            let (mut cpu, bus) = setup(Variant::Cmos65C02, code);
+            bus.0.borrow_mut()[0x0010..0x0012].copy_from_slice(&[0x3C, 0x02]);
+            bus.0.borrow_mut()[0x023C] = 0x80;
+            cpu.reg.a = 0x0F;
+            let used = step(&mut cpu, &bus);
+            let mem = bus.0.borrow();
+            let got = (mem[0x0010], mem[0x023C], cpu.reg.a, cpu.reg.flags.z());
+            assert_eq!(got, (m, ind, a, z), "{:02X?}", code);
+            assert_eq!(used, cycles, "{:02X?}", code);
+            assert_eq!(cpu.reg.pc, 0x0200 + code.len() as u16);
+        }
+        // TSB and TRB set Z from A AND M
+        let (mut cpu, bus) = setup(Variant::Cmos65C02, &[0x04, 0x10]);
+        cpu.reg.a = 0xC0;
+        step(&mut cpu, &bus);
+        assert!(cpu.reg.flags.z());
+
+        // BBR and BBS on $3C at $10, branching by +$10
+        let cases = [
+            (0x0F, 0x0213, 6), // BBR0: bit 0 clear, taken
+            (0x2F, 0x0203, 5), // BBR2: bit 2 set
+            (0xAF, 0x0213, 6), // BBS2: bit 2 set, taken
+            (0xFF, 0x0203, 5), // BBS7: bit 7 clear
+        ];
+        for (op, pc, cycles) in cases {
+            let (mut cpu, bus) = setup(Variant::Cmos65C02, &[op, 0x10, 0x10]);
+            bus.0.borrow_mut()[0x0010] = 0x3C;
+            assert_eq!(step(&mut cpu, &bus), cycles, "${:02X}", op);
+            assert_eq!(cpu.reg.pc, pc, "${:02X}", op);
+        }
+    }
+
+    #[test]
+    fn cmos_decode() {
+        let codes = [
+            (0x04, "TSB", Mode::Zp, 5),
+            (0x1C, "TRB", Mode::Abs, 6),
+            (0x9E, "STZ", Mode::AbsX, 5),
+            (0x57, "RMB5", Mode::Zp, 5),
+            (0xF7, "SMB7", Mode::Zp, 5),
+            (0x4F, "BBR4", Mode::ZpRel, 5),
+            (0xBF, "BBS3", Mode::ZpRel, 5),
+            (0x12, "ORA", Mode::ZpInd, 5),
+            (0x7C, "JMP", Mode::AbsXInd, 6),
+            (0x6C, "JMP", Mode::Ind, 6),
+            (0xA9, "LDA", Mode::Immd, 2),
+        ];
+        for (op, name, mode, cyc) in codes {
+            let code = cmos::decode(op);
+            assert_eq!((code.name, code.mode, code.cyc), (name, mode, cyc));
+        }
+        // the jams and unofficial opcodes are gone, reserved slots are NOPs
+        for code in cmos::OPCODES.iter() {
+            let reserved = code.kind != Kind::Official;
+            assert!(!reserved || code.name == "NOP", "${:02X}", code.op);
+        }
+    }
+
+    #[test]
+    fn jmp_indirect_page_wrap() {
+        for (variant, target, cycles) in [
+            (Variant::Nmos6502, 0x3412, 5),
+            (Variant::Cmos65C02, 0x5612, 6),
+        ] {
+            let (mut cpu, bus) = setup(variant, &[0x6C, 0xFF, 0x10]);
+            bus.0.borrow_mut()[0x10FF] = 0x12;
+            bus.0.borrow_mut()[0x1000] = 0x34;
+            bus.0.borrow_mut()[0x1100] = 0x56;
+            assert_eq!(step(&mut cpu, &bus), cycles);
+            assert_eq!(cpu.reg.pc, target, "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn pointer_high_matches_jmp() {
+        // pointer, then the high byte address on NMOS and on the 65C02
+        let cases = [
+            (0x10FF, 0x1000, 0x1100),
+            (0x1080, 0x1081, 0x1081),
+            (0xFFFF, 0xFF00, 0x0000),
+        ];
+        for (ptr, nmos, cmos) in cases {
+            for (variant, high) in
+                [(Variant::Nmos6502, nmos), (Variant::Cmos65C02, cmos)]
+            {
+                assert_eq!(pointer_high(ptr, variant), high);
+                let [lo, hi] = u16::to_le_bytes(ptr);
+                let (mut cpu, bus) = setup(variant, &[0x6C, lo, hi]);
+                {
+                    // $56 where the other variant reads the high byte
+                    let mut mem = bus.0.borrow_mut();
+                    mem[nmos as usize] = 0x56;
+                    mem[cmos as usize] = 0x56;
+                    mem[high as usize] = 0x34;
+                    mem[ptr as usize] = 0x12;
+                }
+                step(&mut cpu, &bus);
+                assert_eq!(cpu.reg.pc, 0x3412, "{:?} ${:04X}", variant, ptr);
+            }
+        }
+    }
+
+    /// flat 64 KiB RAM that logs every access
+    struct Log {
+        ram: Ram,
+        log: RefCell<Vec<(u16, Option<u8>)>>,
+    }
+
+    impl Bus for Log {
+        fn get(&self, addr: u16) -> Result<u8, AddressError> {
+            self.log.borrow_mut().push((addr, None));
+            self.ram.get(addr)
+        }
+
+        fn set(&self, addr: u16, val: u8) -> Result<(), AddressError> {
+            self.log.borrow_mut().push((addr, Some(val)));
+            self.ram.set(addr, val)
+        }
+
+        fn get_pointer(&self, addr: u16) -> Result<u16, AddressError> {
+            self.ram.get_pointer(addr)
+        }
+
+        fn get_window(
+            &self,
+            addr: u16,
+            size: u16,
+        ) -> Result<&[u8], AddressError> {
+            self.ram.get_window(addr, size)
+        }
+
+        fn set_window(
+            &self,
+            addr: u16,
+            val: &[u8],
+        ) -> Result<(), AddressError> {
+            self.ram.set_window(addr, val)
+        }
+    }
+
+    /// resolve an operand through the [`Addressing`] impl of its mode
+    fn resolve(
+        mode: Mode,
+        operand: [u8; 2],
+        bus: &dyn Bus,
+        reg: &Registers,
+        variant: Variant,
+    ) -> Resolved {
+        let bus = &mut SharedBus(bus);
+        let word = u16::from_le_bytes(operand);
+        let [lo, hi] = operand;
+        match mode {
+            Mode::Abs => OpAbs { addr: word }.resolve(bus, reg, variant),
+            Mode::AbsX => OpAbsX { addr: word }.resolve(bus, reg, variant),
+            Mode::AbsY => OpAbsY { addr: word }.resolve(bus, reg, variant),
+            Mode::Ind => OpInd { ind: word }.resolve(bus, reg, variant),
+            Mode::IndX => OpIndX { ind: lo }.resolve(bus, reg, variant),
+            Mode::IndY => OpIndY { ind: lo }.resolve(bus, reg, variant),
+            Mode::Rel => OpRel { rel: lo }.resolve(bus, reg, variant),
+            Mode::Zp => OpZp { addr: lo }.resolve(bus, reg, variant),
+            Mode::ZpX => OpZpX { addr: lo }.resolve(bus, reg, variant),
+            Mode::ZpY => OpZpY { addr: lo }.resolve(bus, reg, variant),
+            Mode::ZpInd => OpZpInd { ind: lo }.resolve(bus, reg, variant),
+            Mode::AbsXInd => OpAbsXInd { ind: word }.resolve(bus, reg, variant),
+            Mode::ZpRel => {
+                OpZpRel { addr: lo, rel: hi }.resolve(bus, reg, variant)
+            }
+            _ => unreachable!(),
+        }
+        .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn addressing_matches_cpu() {
+        // loads, jumps and branches of every mode with a memory operand
+        let nmos = [
+            (0xAD, Mode::Abs),
+            (0xBD, Mode::AbsX),
+            (0xB9, Mode::AbsY),
+            (0x6C, Mode::Ind),
+            (0xA1, Mode::IndX),
+            (0xB1, Mode::IndY),
+            (0x90, Mode::Rel),
+            (0xA5, Mode::Zp),
+            (0xB5, Mode::ZpX),
+            (0xB6, Mode::ZpY),
+        ];
+        let cmos = [
+            (0xB2, Mode::ZpInd),
+            (0x7C, Mode::AbsXInd),
+            (0x0F, Mode::ZpRel),
+        ];
+        let mut seed = 0x2A03_u32;
+        let mut rnd = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+        for variant in [Variant::Nmos6502, Variant::Cmos65C02] {
+            let cases = match variant {
+                Variant::Cmos65C02 => [&nmos[..], &cmos[..]].concat(),
+                _ => nmos.to_vec(),
+            };
+            for (op, mode) in cases {
+                for i in 0..32 {
+                    let mut mem: Vec<u8> =
+                        (0..0x10000).map(|_| rnd()).collect();
+                    // pointers and operands at the end of a page
+                    let operand = match i % 2 {
+                        0 => [0xFF, rnd()],
+                        _ => [rnd(), rnd()],
+                    };
+                    mem[0x0200..0x0203]
+                        .copy_from_slice(&[op, operand[0], operand[1]]);
+                    let bus = Log {
+                        ram: Ram(RefCell::new(mem)),
+                        log: RefCell::new(Vec::new()),
+                    };
+                    let mut cpu = Cpu::with_variant(variant);
+                    cpu.reg.pc = 0x0200;
+                    cpu.reg.x = rnd();
+                    cpu.reg.y = rnd();
+                    let reg = Registers {
+                        a: 0,
+                        x: cpu.reg.x,
+                        y: cpu.reg.y,
+                        sp: cpu.reg.sp,
+                        pc: 0x0200 + OPCODES[op as usize].n as u16,
+                        flags: Flags {
+                            flags: cpu.reg.flags.flags,
+                        },
+                    };
+                    let res = resolve(mode, operand, &bus, &reg, variant);
+                    bus.log.borrow_mut().clear();
+                    step(&mut cpu, &bus);
+                    let what =
+                        format!("{:?} {:?} {:?}", variant, mode, operand);
+                    let log = bus.log.borrow();
+                    match mode {
+                        Mode::Ind | Mode::AbsXInd | Mode::Rel => {
+                            assert_eq!(cpu.reg.pc, res.addr, "{}", what)
+                        }
+                        // the tested byte is read after the two operands
+                        Mode::ZpRel => {
+                            assert_eq!(log[2], (res.addr, None), "{}", what)
+                        }
+                        _ => assert_eq!(
+                            log.last(),
+                            Some(&(res.addr, None)),
+                            "{}",
+                            what
+                        ),
+                    }
+                    // indexed loads only read the dummy address on a crossing
+                    let paid = res.crossed
+                        || !matches!(
+                            mode,
+                            Mode::AbsX | Mode::AbsY | Mode::IndY
+                        );
+                    if let Some(dummy) = res.dummy.filter(|_| paid) {
+                        assert!(log.contains(&(dummy, None)), "{}", what);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resolved_page_crossing() {
+        let (_, bus) = setup(Variant::Nmos6502, &[]);
+        // pointer $12F0 at $80
+        bus.0.borrow_mut()[0x0080..0x0082].copy_from_slice(&[0xF0, 0x12]);
+        // operand, index in X and Y, effective and dummy address
+        let cases = [
+            (Mode::AbsX, 0x12F0, 0x0F, 0x12FF, false, Some(0x12FF)),
+            (Mode::AbsX, 0x12F0, 0x10, 0x1300, true, Some(0x1200)),
+            (Mode::AbsY, 0x12F0, 0x0F, 0x12FF, false, Some(0x12FF)),
+            (Mode::AbsY, 0x12F0, 0x20, 0x1310, true, Some(0x1210)),
+            (Mode::IndY, 0x0080, 0x0F, 0x12FF, false, Some(0x12FF)),
+            (Mode::IndY, 0x0080, 0x10, 0x1300, true, Some(0x1200)),
+            // zero page indexing wraps and never crosses
+            (Mode::IndX, 0x0070, 0x10, 0x12F0, false, Some(0x0070)),
+            (Mode::IndX, 0x00F0, 0x90, 0x12F0, false, Some(0x00F0)),
+            (Mode::ZpX, 0x00F0, 0x0F, 0x00FF, false, Some(0x00F0)),
+            (Mode::ZpX, 0x00F0, 0x10, 0x0000, false, Some(0x00F0)),
+            (Mode::ZpY, 0x00F0, 0x10, 0x0000, false, Some(0x00F0)),
+            // branches from $0202
+            (Mode::Rel, 0x007F, 0x00, 0x0281, false, None),
+            (Mode::Rel, 0x00FD, 0x00, 0x01FF, true, Some(0x02FF)),
+        ];
+        for (mode, operand, idx, addr, crossed, dummy) in cases {
+            let reg = Registers {
+                a: 0,
+                x: idx,
+                y: idx,
+                sp: 0xFD,
+                pc: 0x0202,
+                flags: Flags { flags: 0 },
+            };
+            let operand = u16::to_le_bytes(operand);
+            let want = Resolved {
+                addr,
+                crossed,
+                dummy,
+            };
+            let got = resolve(mode, operand, &bus, &reg, Variant::Nmos6502);
+            assert_eq!(got, want, "{:?} {:?} {:02X}", mode, operand, idx);
+        }
+    }
+
+    /// cpu with code at $0200 and the NMI, RESET and IRQ handlers at $4000,
+    /// $5000 and $3000, each starting with a NOP
+    fn vectors(code: &[u8], p: u8) -> (Cpu, Ram) {
+        let (mut cpu, bus) = setup(Variant::Nmos6502, code);
+        bus.0.borrow_mut()[NMI_VECTOR as usize..]
+            .copy_from_slice(&[0x00, 0x40, 0x00, 0x50, 0x00, 0x30]);
+        for handler in [0x3000, 0x4000, 0x5000] {
+            bus.0.borrow_mut()[handler] = 0xEA;
+        }
+        cpu.reg.flags = Flags { flags: p };
+        (cpu, bus)
+    }
+
+    /// the three bytes pushed by the last interrupt: status, PC low, PC high
+    fn pushed(cpu: &Cpu, bus: &Ram) -> (u8, u16) {
+        let mem = bus.0.borrow();
+        let sp = cpu.reg.sp as usize;
+        let pc = u16::from_le_bytes([mem[0x0102 + sp], mem[0x0103 + sp]]);
+        (mem[0x0101 + sp], pc)
+    }
+
+    #[test]
+    fn interrupt_sequences() {
+        const B: u8 = 0x10;
+        const U: u8 = 0x20;
+        // BRK skips its padding byte and pushes B set
+        let (mut cpu, bus) = vectors(&[0x00, 0xFF], SEC);
+        assert_eq!(step(&mut cpu, &bus), 7);
+        assert_eq!(cpu.reg.pc, 0x3000);
+        assert_eq!(pushed(&cpu, &bus), (SEC | B | U, 0x0202));
+        assert!(cpu.reg.flags.i());
+
+        // IRQ pushes B clear and returns to the next instruction
+        let (mut cpu, bus) = vectors(&[0xEA], SEC);
+        cpu.set_irq(true);
+        step(&mut cpu, &bus);
+        assert_eq!(step(&mut cpu, &bus), 7);
+        assert_eq!(cpu.reg.pc, 0x3000);
+        assert_eq!(pushed(&cpu, &bus), (SEC | U, 0x0201));
+        // the handler runs with I set, the level is no longer serviced
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x3001);
+
+        // NMI as IRQ, even with I set
+        let (mut cpu, bus) = vectors(&[0xEA], 0x04);
+        cpu.nmi();
+        step(&mut cpu, &bus);
+        assert_eq!(step(&mut cpu, &bus), 7);
+        assert_eq!(cpu.reg.pc, 0x4000);
+        assert_eq!(pushed(&cpu, &bus), (0x04 | U, 0x0201));
+    }
+
+    #[test]
+    fn nmi_hijacks_brk_and_irq() {
+        // BRK pushes B set but continues at the NMI handler
+        let (mut cpu, bus) = vectors(&[0x00, 0xFF], 0);
+        cpu.nmi();
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x4000);
+        assert_eq!(pushed(&cpu, &bus), (0x30, 0x0202));
+        // the NMI was taken, it is not serviced again
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x4001);
+
+        // an NMI arriving during the IRQ sequence takes it over
+        let (mut cpu, bus) = vectors(&[0xEA], 0);
+        cpu.set_irq(true);
+        step(&mut cpu, &bus);
+        cpu.nmi();
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x4000);
+        assert_eq!(pushed(&cpu, &bus), (0x20, 0x0201));
+    }
+
+    #[test]
+    fn reset() {
+        let (mut cpu, bus) = vectors(&[], 0);
+        cpu.nmi();
+        assert_eq!(cpu.reset(&mut SharedBus(&bus)).ok(), Some(7));
+        assert_eq!(cpu.reg.pc, 0x5000);
+        assert_eq!(cpu.reg.sp, 0xFD - 3);
+        assert!(cpu.reg.flags.i());
+        // nothing is written to the stack and the NMI is dropped
+        assert!(bus.0.borrow()[0x0100..0x0200].iter().all(|&b| b == 0));
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x5001);
+    }
+
+    #[test]
+    fn kil_jams_until_reset() {
+        let (mut cpu, bus) = vectors(&[0xEA, 0x02, 0xA9, 0x01], 0);
+        step(&mut cpu, &bus);
+        let fault = Fault {
+            error: AddressError::Halt(0x0201),
+            access: Access::Fetch,
+            pc: 0x0201,
+            op: Some(0x02),
+            cycle: 4,
+        };
+        assert_eq!(cpu.step(&mut SharedBus(&bus)), Err(fault));
+        assert_eq!(cpu.jammed(), Some(fault));
+        assert_eq!(cpu.reg.pc, 0x0201);
+        // nothing runs anymore, not even an NMI
+        cpu.nmi();
+        for _ in 0..3 {
+            assert_eq!(cpu.step(&mut SharedBus(&bus)), Err(fault));
+            assert_eq!(cpu.tick(&mut SharedBus(&bus)), Err(fault));
+        }
+        assert_eq!((cpu.reg.pc, cpu.reg.a, cpu.reg.sp), (0x0201, 0, 0xFD));
+        // RESET recovers
+        assert_eq!(cpu.reset(&mut SharedBus(&bus)).ok(), Some(7));
+        assert_eq!(cpu.jammed(), None);
+        assert_eq!(step(&mut cpu, &bus), 2);
+        assert_eq!(cpu.reg.pc, 0x5001);
+    }
+
+    /// cpu with RAM on $0000-$00FF and code at $0200-$07FF, ROM on
+    /// $8000-$FFFF and nothing else
+    fn sparse(code: &[u8]) -> (Cpu, MemoryMap) {
+        let mut map = MemoryMap::new()
+            .with_ram(0x0000, 0x00FF, 0x100)
+            .with_ram(0x0200, 0x07FF, 0x600)
+            .with_rom(0x8000, 0xFFFF, vec![0; 0x8000]);
+        map.set_window(0x0200, code).unwrap();
+        let mut cpu = Cpu::with_variant(Variant::Nmos6502);
+        cpu.reg.pc = 0x0200;
+        (cpu, map)
+    }
+
+    #[test]
+    fn faults() {
+        use AddressError::*;
+        // code, instructions run before the fault, then the fault
+        let cases = [
+            // LDA #$01, STA $8000
+            (
+                &[0xA9, 0x01, 0x8D, 0x00, 0x80][..],
+                1,
+                (ReadOnly(0x8000), Access::Write, 0x0202, Some(0x8D), 6),
+            ),
+            // LDA $6000
+            (
+                &[0xAD, 0x00, 0x60],
+                0,
+                (Unavailable(0x6000), Access::Read, 0x0200, Some(0xAD), 4),
+            ),
+            // JMP $6000, then the opcode fetch
+            (
+                &[0x4C, 0x00, 0x60],
+                1,
+                (Unavailable(0x6000), Access::Fetch, 0x6000, None, 4),
+            ),
+            // PHA
+            (
+                &[0x48],
+                0,
+                (Unavailable(0x01FD), Access::Stack, 0x0200, Some(0x48), 3),
+            ),
+        ];
+        for (code, skip, (error, access, pc, op, cycle)) in cases {
+            let (mut cpu, mut bus) = sparse(code);
+            for _ in 0..skip {
+                assert!(cpu.step(&mut bus).is_ok());
+            }
+            let fault = Fault {
+                error,
+                access,
+                pc,
+                op,
+                cycle,
+            };
+            assert_eq!(cpu.step(&mut bus), Err(fault), "{:02X?}", code);
+        }
+
+        // RESET faults on the vector, without an opcode
+        let mut cpu = Cpu::new();
+        let mut bus = MemoryMap::new();
+        let fault = cpu.reset(&mut bus).unwrap_err();
+        assert_eq!((fault.access, fault.op), (Access::Read, None));
+        assert_eq!(fault.error, Unavailable(RESET_VECTOR));
+
+        let fault = Fault {
+            error: ReadOnly(0x8000),
+            access: Access::Write,
+            pc: 0x0202,
+            op: Some(0x8D),
+            cycle: 6,
+        };
+        assert_eq!(
+            fault.to_string(),
+            format!(
+                "<Fault> write at PC $0202 (opcode $8D), cycle 6: {}",
+                ReadOnly(0x8000)
+            )
+        );
+        let fault = Fault { op: None, ..fault };
+        assert!(fault
+            .to_string()
+            .starts_with("<Fault> write at PC $0202, cycle 6: "));
+    }
+
+    #[test]
+    fn fault_policy() {
+        use AddressError::*;
+        // LDA #$42, STA $8000, LDA $6000
+        let code = [0xA9, 0x42, 0x8D, 0x00, 0x80, 0xAD, 0x00, 0x60];
+
+        // Abort returns the fault and leaves the cpu running
+        let (mut cpu, mut bus) = sparse(&code);
+        cpu.step(&mut bus).unwrap();
+        let fault = cpu.step(&mut bus).unwrap_err();
+        assert_eq!(fault.error, ReadOnly(0x8000));
+        assert_eq!((cpu.jammed(), cpu.suppressed()), (None, 0));
+
+        // Ignore drops the write and reads the open bus, here the high
+        // byte of the operand
+        let (mut cpu, mut bus) = sparse(&code);
+        cpu.set_policy(FaultPolicy::LENIENT);
+        for _ in 0..3 {
+            cpu.step(&mut bus).unwrap();
+        }
+        assert_eq!(cpu.reg.a, 0x60);
+        assert_eq!(bus.get(0x8000), Ok(0x00));
+        assert_eq!(cpu.suppressed(), 2);
+        let last = Fault {
+            error: Unavailable(0x6000),
+            access: Access::Read,
+            pc: 0x0205,
+            op: Some(0xAD),
+            cycle: 10,
+        };
+        assert_eq!(cpu.last_suppressed(), Some(last));
+        cpu.clear_suppressed();
+        assert_eq!((cpu.suppressed(), cpu.last_suppressed()), (0, None));
+
+        // Halt jams the cpu until RESET
+        let (mut cpu, mut bus) = sparse(&code);
+        cpu.set_policy(FaultPolicy {
+            read_only: Action::Halt,
+            ..FaultPolicy::STRICT
+        });
+        cpu.step(&mut bus).unwrap();
+        let fault = cpu.step(&mut bus).unwrap_err();
+        assert_eq!(fault.error, ReadOnly(0x8000));
+        assert_eq!(cpu.jammed(), Some(fault));
+        assert_eq!(cpu.step(&mut bus), Err(fault));
+        assert_eq!(cpu.tick(&mut bus), Err(fault));
+        bus = bus.with_ram(0xFFFC, 0xFFFD, 2);
+        bus.set_window(0xFFFC, &[0x00, 0x02]).unwrap();
+        assert_eq!(cpu.reset(&mut bus), Ok(7));
+        assert_eq!(cpu.jammed(), None);
+
+        // dummy reads never fault: LDA $01F0,X reads $0100 first
+        for policy in [FaultPolicy::STRICT, FaultPolicy::LENIENT] {
+            let (mut cpu, mut bus) = sparse(&[0xBD, 0xF0, 0x01]);
+            cpu.set_policy(policy);
+            cpu.reg.x = 0x10;
+            assert_eq!(cpu.step(&mut bus), Ok(5));
+            assert_eq!((cpu.reg.a, cpu.suppressed()), (0xBD, 0));
+        }
+    }
+
+    #[test]
+    fn tick_matches_step() {
+        let variants = [
+            (Variant::Nmos6502, &OPCODES),
+            (Variant::Cmos65C02, &cmos::OPCODES),
+        ];
+        for (variant, table) in variants {
+            for code in table.iter().filter(|code| code.kind != Kind::Jam) {
+                // the instruction, then the IRQ sequence or the next one
+                let run = |ticked: bool| {
+                    let (mut cpu, ram) =
+                        setup(variant, &[code.op, 0xF0, 0x10, 0xEA]);
+                    ram.0.borrow_mut()[0x00F0..0x00F2]
+                        .copy_from_slice(&[0xF0, 0x10]);
+                    let bus = Log {
+                        ram,
+                        log: RefCell::new(Vec::new()),
+                    };
+                    cpu.reg.x = 0x20;
+                    cpu.reg.y = 0x20;
+                    cpu.reg.flags = U;
+                    cpu.set_irq(true);
+                    let mut cycles = Vec::new();
+                    for _ in 0..2 {
+                        cycles.push(match ticked {
+                            true => {
+                                (1..).find(|_| tick(&mut cpu, &bus)).unwrap()
+                            }
+                            false => step(&mut cpu, &bus),
+                        });
+                    }
+                    let mem = bus.ram.0.into_inner();
+                    (cycles, cpu.reg, bus.log.into_inner(), mem)
+                };
+                let (stepped, ticked) = (run(false), run(true));
+                let what =
+                    format!("{:?} ${:02X} {}", variant, code.op, code.name);
+                assert_eq!(stepped.0, ticked.0, "{}", what);
+                assert_eq!(stepped.1, ticked.1, "{}", what);
+                assert_eq!(stepped.2, ticked.2, "{}", what);
+                assert!(stepped.3 == ticked.3, "{}", what);
+            }
+        }
+    }
+
+    #[test]
+    fn stall() {
+        // the stolen cycles are added to the next step
+        let (mut cpu, bus) = setup(Variant::Nmos6502, &[0xEA, 0xEA]);
+        cpu.stall(3);
+        assert_eq!(step(&mut cpu, &bus), 5);
+        assert_eq!(step(&mut cpu, &bus), 2);
+
+        // or spent by ticks that leave the bus alone
+        let (mut cpu, ram) = setup(Variant::Nmos6502, &[0xEA]);
+        let bus = Log {
+            ram,
+            log: RefCell::new(Vec::new()),
+        };
+        cpu.stall(3);
+        for _ in 0..3 {
+            assert!(!tick(&mut cpu, &bus));
+        }
+        assert!(bus.log.borrow().is_empty());
+        assert_eq!(cpu.reg.pc, 0x0200);
+        assert!(!tick(&mut cpu, &bus));
+        assert!(tick(&mut cpu, &bus));
+        assert_eq!(cpu.reg.pc, 0x0201);
+        assert_eq!(*bus.log.borrow(), [(0x0200, None), (0x0201, None)]);
+    }
+
+    #[test]
+    fn tick_nmi_edge() {
+        // an edge during LDA $1234 is taken after it, Z is set by the load
+        let (mut cpu, bus) = vectors(&[0xAD, 0x34, 0x12, 0xEA], 0);
+        assert!(!tick(&mut cpu, &bus));
+        assert!(!tick(&mut cpu, &bus));
+        cpu.nmi();
+        assert!(!tick(&mut cpu, &bus));
+        assert!(tick(&mut cpu, &bus));
+        assert_eq!(cpu.reg.pc, 0x0203);
+        assert_eq!((1..).find(|_| tick(&mut cpu, &bus)), Some(7));
+        assert_eq!(cpu.reg.pc, 0x4000);
+        assert_eq!(pushed(&cpu, &bus), (0x22, 0x0203));
+
+        // an edge after the last cycle waits for the next instruction
+        let (mut cpu, bus) = vectors(&[0xAD, 0x34, 0x12, 0xEA], 0);
+        while !tick(&mut cpu, &bus) {}
+        cpu.nmi();
+        assert_eq!((1..).find(|_| tick(&mut cpu, &bus)), Some(2));
+        assert_eq!(cpu.reg.pc, 0x0204);
+        assert_eq!((1..).find(|_| tick(&mut cpu, &bus)), Some(7));
+        assert_eq!(pushed(&cpu, &bus), (0x22, 0x0204));
+    }
+
+    /// cpu with the IRQ line active and the IRQ handler at $3000
+    fn irq(code: &[u8], p: u8) -> (Cpu, Ram) {
+        let (mut cpu, bus) = vectors(code, p);
+        cpu.set_irq(true);
+        (cpu, bus)
+    }
+
+    #[test]
+    fn irq_latency() {
+        const I: u8 = 0x04;
+        // CLI: the next instruction runs before the IRQ
+        let (mut cpu, bus) = irq(&[0x58, 0xEA, 0xEA], I);
+        step(&mut cpu, &bus);
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x0202);
+        assert_eq!(step(&mut cpu, &bus), 7);
+        assert_eq!(cpu.reg.pc, 0x3000);
+
+        // SEI: the IRQ polled before it is still taken
+        let (mut cpu, bus) = irq(&[0x78, 0xEA], 0);
+        step(&mut cpu, &bus);
+        assert!(cpu.reg.flags.i());
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x3000);
+
+        // PLP clearing I: as CLI
+        let (mut cpu, bus) = irq(&[0x28, 0xEA, 0xEA], I);
+        cpu.reg.sp = 0xFE;
+        bus.0.borrow_mut()[0x01FF] = 0x20;
+        step(&mut cpu, &bus);
+        assert!(!cpu.reg.flags.i());
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x0202);
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x3000);
+
+        // PLP setting I: the IRQ polled before it is still taken
+        let (mut cpu, bus) = irq(&[0x28, 0xEA], 0);
+        cpu.reg.sp = 0xFE;
+        bus.0.borrow_mut()[0x01FF] = 0x24;
+        step(&mut cpu, &bus);
+        step(&mut cpu, &bus);
+        assert_eq!(cpu.reg.pc, 0x3000);
+    }
+
+    #[test]
+    fn call() {
+        // STA $10, STX $11, PHA, PLA, RTS
+        let (mut cpu, ram) = setup(Variant::Nmos6502, &[]);
+        let code = [0x85, 0x10, 0x86, 0x11, 0x48, 0x68, 0x60];
+        ram.set_window(0x0300, &code).unwrap();
+        let mut bus = SharedBus(&ram);
+        assert_eq!(cpu.call(&mut bus, 0x0300, 0x12, 0x34, 1000), Ok(19));
+        assert_eq!((ram.get(0x10), ram.get(0x11)), (Ok(0x12), Ok(0x34)));
+        assert_eq!((cpu.reg.sp, cpu.reg.pc), (0xFD, CALL_SENTINEL));
+        // a nested JSR returns through the routine first
+        ram.set_window(0x0400, &[0x20, 0x00, 0x03, 0x60]).unwrap();
+        assert_eq!(cpu.call(&mut bus, 0x0400, 0, 0, 1000), Ok(31));
+        assert_eq!(cpu.reg.sp, 0xFD);
+
+        // JMP $0300 never returns
+        ram.set_window(0x0300, &[0x4C, 0x00, 0x03]).unwrap();
+        let used = cpu.call(&mut bus, 0x0300, 0, 0, 100);
+        assert_eq!(used, Err(CallError::Timeout(102)));
+        assert_eq!((cpu.reg.sp, cpu.reg.pc), (0xFB, 0x0300));
+
+        // LDA $6000 faults inside the routine
+        let (mut cpu, mut map) = sparse(&[]);
+        map.set_window(0x0300, &[0xAD, 0x00, 0x60, 0x60]).unwrap();
+        let fault = Fault {
+            error: AddressError::Unavailable(0x6000),
+            access: Access::Read,
+            pc: 0x0300,
+            op: Some(0xAD),
+            cycle: 4,
+        };
+        // the sparse map has no stack to push the return address on
+        assert!(matches!(
+            cpu.call(&mut map, 0x0300, 0, 0, 1000),
+            Err(CallError::Fault(Fault {
+                access: Access::Stack,
+                ..
+            }))
+        ));
+        let mut map = map.with_ram(0x0100, 0x01FF, 0x100);
+        let result = cpu.call(&mut map, 0x0300, 0, 0, 1000);
+        assert_eq!(result, Err(CallError::Fault(fault)));
+    }
+}
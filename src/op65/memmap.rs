@@ -0,0 +1,247 @@
+//! Memory map
+//!
+//! [`MemoryMap`] composes an address space from regions of RAM, ROM and I/O
+//! devices and implements [`BusMut`] over them. A region is mirrored across
+//! its whole address range, so the 2 KiB NES RAM is one region of
+//! `$0000-$1FFF`. Regions registered later overlay the earlier ones, and the
+//! map produces the `Unavailable`, `ReadOnly` and `WriteOnly` errors by
+//! itself. Windows are copied out, so they may span regions and mirrors.
+
+use crate::op65::context::{AddressError, BusMut};
+
+/// Device is a memory-mapped I/O device. Both accesses get the absolute
+/// address. A device is write-only or read-only unless it overrides both.
+pub trait Device {
+    /// read a register of the device
+    fn read(&mut self, addr: u16) -> Result<u8, AddressError> {
+        Err(AddressError::WriteOnly(addr))
+    }
+
+    /// write a register of the device
+    fn write(&mut self, addr: u16, _val: u8) -> Result<(), AddressError> {
+        Err(AddressError::ReadOnly(addr))
+    }
+}
+
+/// write-only registers served by a callback
+struct WriteOnly<F: FnMut(u16, u8)>(F);
+
+impl<F: FnMut(u16, u8)> Device for WriteOnly<F> {
+    fn write(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        (self.0)(addr, val);
+        Ok(())
+    }
+}
+
+/// storage behind a region
+enum Backing {
+    Ram(Vec<u8>),
+    Rom(Vec<u8>),
+    Device(Box<dyn Device>),
+}
+
+/// a mapped address range
+struct Region {
+    start: u16,
+    backing: Backing,
+}
+
+/// index of a region offset in mirrored storage of `len` bytes
+fn mirror(offset: u16, len: usize) -> usize {
+    offset as usize % len
+}
+
+/// slot of an unmapped address
+const UNMAPPED: u8 = u8::MAX;
+
+/// MemoryMap is a [`BusMut`] composed of RAM, ROM and device regions
+pub struct MemoryMap {
+    regions: Vec<Region>,
+    /// region index of every address, so lookups take constant time
+    slots: Vec<u8>,
+}
+
+impl Default for MemoryMap {
+    fn default() -> MemoryMap {
+        MemoryMap::new()
+    }
+}
+
+impl MemoryMap {
+    /// create an empty map, every address is unavailable
+    pub fn new() -> MemoryMap {
+        MemoryMap {
+            regions: Vec::new(),
+            slots: vec![UNMAPPED; 0x10000],
+        }
+    }
+
+    /// map `size` bytes of zeroed RAM mirrored across `start..=end`
+    ///
+    /// Panics if the range is inverted or `size` is 0.
+    pub fn with_ram(self, start: u16, end: u16, size: usize) -> Self {
+        assert!(size > 0, "RAM region of 0 bytes");
+        self.with(start, end, Backing::Ram(vec![0; size]))
+    }
+
+    /// map read-only data mirrored across `start..=end`
+    ///
+    /// Panics if the range is inverted or `data` is empty.
+    pub fn with_rom(self, start: u16, end: u16, data: Vec<u8>) -> Self {
+        assert!(!data.is_empty(), "ROM region of 0 bytes");
+        self.with(start, end, Backing::Rom(data))
+    }
+
+    /// map a device on `start..=end`
+    ///
+    /// Panics if the range is inverted.
+    pub fn with_device(
+        self,
+        start: u16,
+        end: u16,
+        device: Box<dyn Device>,
+    ) -> Self {
+        self.with(start, end, Backing::Device(device))
+    }
+
+    /// map write-only registers on `start..=end`, writes call `write` with
+    /// the address and value
+    ///
+    /// Panics if the range is inverted.
+    pub fn with_write_only(
+        self,
+        start: u16,
+        end: u16,
+        write: impl FnMut(u16, u8) + 'static,
+    ) -> Self {
+        self.with_device(start, end, Box::new(WriteOnly(write)))
+    }
+
+    fn with(mut self, start: u16, end: u16, backing: Backing) -> Self {
+        assert!(start <= end, "region ${:04X}-${:04X} inverted", start, end);
+        let slot = self.regions.len();
+        assert!(slot < UNMAPPED as usize, "too many regions");
+        self.slots[start as usize..=end as usize].fill(slot as u8);
+        self.regions.push(Region { start, backing });
+        self
+    }
+
+    /// region mapped at an address
+    fn region(&mut self, addr: u16) -> Result<&mut Region, AddressError> {
+        match self.slots[addr as usize] {
+            UNMAPPED => Err(AddressError::Unavailable(addr)),
+            slot => Ok(&mut self.regions[slot as usize]),
+        }
+    }
+}
+
+// BusMut for MemoryMap
+impl BusMut for MemoryMap {
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        let region = self.region(addr)?;
+        let offset = addr - region.start;
+        match &mut region.backing {
+            Backing::Ram(data) | Backing::Rom(data) => {
+                Ok(data[mirror(offset, data.len())])
+            }
+            Backing::Device(device) => device.read(addr),
+        }
+    }
+
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        let region = self.region(addr)?;
+        let offset = addr - region.start;
+        match &mut region.backing {
+            Backing::Ram(data) => {
+                let len = data.len();
+                data[mirror(offset, len)] = val;
+                Ok(())
+            }
+            Backing::Rom(_) => Err(AddressError::ReadOnly(addr)),
+            Backing::Device(device) => device.write(addr, val),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn mirroring() {
+        // 2 KiB of RAM on $0000-$1FFF, 3 bytes of ROM on $8000-$8007
+        let mut map = MemoryMap::new()
+            .with_ram(0x0000, 0x1FFF, 0x0800)
+            .with_rom(0x8000, 0x8007, vec![1, 2, 3]);
+        map.set(0x0012, 0xAB).ok();
+        for addr in [0x0012, 0x0812, 0x1012, 0x1812] {
+            assert_eq!(map.get(addr).ok(), Some(0xAB), "${:04X}", addr);
+        }
+        map.set(0x1FFF, 0xCD).ok();
+        assert_eq!(map.get(0x07FF).ok(), Some(0xCD));
+        let rom: Vec<_> =
+            (0x8000..=0x8007).map(|addr| map.get(addr).ok()).collect();
+        assert_eq!(rom, [1, 2, 3, 1, 2, 3, 1, 2].map(Some));
+        assert!(matches!(map.get(0x2000), Err(AddressError::Unavailable(_))));
+        assert!(matches!(map.get(0x8008), Err(AddressError::Unavailable(_))));
+    }
+
+    #[test]
+    fn read_only_rom() {
+        let mut map =
+            MemoryMap::new().with_rom(0x8000, 0xFFFF, vec![0xEA; 0x10]);
+        assert!(matches!(map.set(0x8000, 0), Err(AddressError::ReadOnly(_))));
+        assert!(matches!(
+            map.set_window(0x8000, &[0]),
+            Err(AddressError::ReadOnly(_))
+        ));
+        assert_eq!(map.get(0x8000).ok(), Some(0xEA));
+        // windows are copied out, so they may wrap around the mirror
+        assert_eq!(map.get_window(0x800C, 8).ok(), Some(vec![0xEA; 8]));
+    }
+
+    #[test]
+    fn write_only() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        let mut map = MemoryMap::new().with_write_only(
+            0x4000,
+            0x4017,
+            move |addr, val| sink.borrow_mut().push((addr, val)),
+        );
+        assert!(map.set(0x4000, 0x3F).is_ok());
+        assert!(map.set(0x4017, 0x40).is_ok());
+        assert_eq!(*log.borrow(), [(0x4000, 0x3F), (0x4017, 0x40)]);
+        assert!(matches!(map.get(0x4000), Err(AddressError::WriteOnly(_))));
+    }
+
+    #[test]
+    fn later_regions_override() {
+        let mut map = MemoryMap::new()
+            .with_ram(0x0000, 0xFFFF, 0x10000)
+            .with_rom(0x8000, 0x8FFF, vec![0x42])
+            .with_ram(0x8800, 0x88FF, 0x100);
+        map.set_window(0x7FFE, &[1, 2]).ok();
+        assert_eq!(map.get(0x7FFF).ok(), Some(2));
+        assert_eq!(map.get(0x8000).ok(), Some(0x42));
+        assert!(map.set(0x8000, 0).is_err());
+        assert!(map.set(0x8800, 7).is_ok());
+        assert_eq!(map.get(0x8800).ok(), Some(7));
+        assert_eq!(map.get(0x8900).ok(), Some(0x42));
+        // past the ROM the first RAM region shows through again
+        assert!(map.set(0x9000, 9).is_ok());
+        assert_eq!(map.get(0x9000).ok(), Some(9));
+    }
+    #[test]
+    #[should_panic(expected = "too many regions")]
+    fn region_limit() {
+        // the slot $FF marks unmapped addresses, 255 regions fit
+        let mut map =
+            (0..255).fold(MemoryMap::new(), |map, i| map.with_ram(i, i, 1));
+        assert!(map.set(0x00FE, 1).is_ok());
+        assert!(map.get(0x00FF).is_err());
+        let _ = map.with_ram(0x00FF, 0x00FF, 1);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod addressing;
 pub mod context;
+pub mod cpu;
+pub mod memmap;
 pub mod opcodes;
+pub mod openbus;
@@ -11,7 +11,11 @@
 //! - <https://www.nesdev.org/wiki/Programming_with_unofficial_opcodes>
 //! - <https://www.oxyron.de/html/opcodes02.html>
 
+use crate::op65::addressing::Mode;
+use crate::op65::context::{Flags, C, D, I, N, O, Z};
+
 /// 6502 OpCode Definitions
+#[derive(Clone, Copy, Debug)]
 pub struct OpCode {
     /// OpCode
     pub op: u8,
@@ -21,8 +25,48 @@ pub struct OpCode {
     pub n: u8,
     /// Cycles
     pub cyc: u8,
+    /// Addressing Mode
+    pub mode: Mode,
+    /// Official, Unofficial, Unstable or Jam
+    pub kind: Kind,
+    /// +1 cycle when the effective address crosses a page. Branches also
+    /// take +1 cycle when taken, which is not counted here.
+    pub penalty: bool,
+    /// Flags affected
+    pub flags: Flags,
+}
+
+/// Documentation status of an OpCode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// documented by MOS
+    Official,
+    /// undocumented but stable on every chip
+    Unofficial,
+    /// undocumented and depending on the chip, temperature or bus state
+    Unstable,
+    /// halts the cpu (KIL/JAM)
+    Jam,
 }
 
+// Flags affected by the instructions
+const NO_FLAGS: Flags = Flags { flags: 0 };
+const NZ: Flags = Flags {
+    flags: N.flags | Z.flags,
+};
+const NZC: Flags = Flags {
+    flags: N.flags | Z.flags | C.flags,
+};
+const NVZ: Flags = Flags {
+    flags: N.flags | O.flags | Z.flags,
+};
+const NVZC: Flags = Flags {
+    flags: N.flags | O.flags | Z.flags | C.flags,
+};
+const NVDIZC: Flags = Flags {
+    flags: N.flags | O.flags | D.flags | I.flags | Z.flags | C.flags,
+};
+
 // 6502 OP Codes
 // ref:
 //  - https://www.masswerk.at/6502/6502_instruction_set.html
@@ -32,906 +76,1510 @@ pub const ADC_ABS: OpCode = OpCode {
     name: "ADC",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ADC_ABS_X: OpCode = OpCode {
     op: 0x7D,
     name: "ADC",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const ADC_ABS_Y: OpCode = OpCode {
     op: 0x79,
     name: "ADC",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const ADC_IMM: OpCode = OpCode {
     op: 0x69,
     name: "ADC",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ADC_IND_Y: OpCode = OpCode {
     op: 0x71,
     name: "ADC",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const ADC_X_IND: OpCode = OpCode {
     op: 0x61,
     name: "ADC",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ADC_ZP: OpCode = OpCode {
     op: 0x65,
     name: "ADC",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ADC_ZP_X: OpCode = OpCode {
     op: 0x75,
     name: "ADC",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const AND_ABS: OpCode = OpCode {
     op: 0x2D,
     name: "AND",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const AND_ABS_X: OpCode = OpCode {
     op: 0x3D,
     name: "AND",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const AND_ABS_Y: OpCode = OpCode {
     op: 0x39,
     name: "AND",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const AND_IMM: OpCode = OpCode {
     op: 0x29,
     name: "AND",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const AND_IND_Y: OpCode = OpCode {
     op: 0x31,
     name: "AND",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const AND_X_IND: OpCode = OpCode {
     op: 0x21,
     name: "AND",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const AND_ZP: OpCode = OpCode {
     op: 0x25,
     name: "AND",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const AND_ZP_X: OpCode = OpCode {
     op: 0x35,
     name: "AND",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const ASL_A: OpCode = OpCode {
     op: 0x0A,
     name: "ASL",
     n: 1,
     cyc: 2,
+    mode: Mode::Acc,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ASL_ABS: OpCode = OpCode {
     op: 0x0E,
     name: "ASL",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ASL_ABS_X: OpCode = OpCode {
     op: 0x1E,
     name: "ASL",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ASL_ZP: OpCode = OpCode {
     op: 0x06,
     name: "ASL",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ASL_ZP_X: OpCode = OpCode {
     op: 0x16,
     name: "ASL",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const BCC_REL: OpCode = OpCode {
     op: 0x90,
     name: "BCC",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BCS_REL: OpCode = OpCode {
     op: 0xB0,
     name: "BCS",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BEQ_REL: OpCode = OpCode {
     op: 0xF0,
     name: "BEQ",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BIT_ABS: OpCode = OpCode {
     op: 0x2C,
     name: "BIT",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZ,
 };
 pub const BIT_ZP: OpCode = OpCode {
     op: 0x24,
     name: "BIT",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZ,
 };
 pub const BMI_REL: OpCode = OpCode {
     op: 0x30,
     name: "BMI",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BNE_REL: OpCode = OpCode {
     op: 0xD0,
     name: "BNE",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BPL_REL: OpCode = OpCode {
     op: 0x10,
     name: "BPL",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BRK: OpCode = OpCode {
     op: 0x00,
     name: "BRK",
     n: 1,
     cyc: 7,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: I,
 };
 pub const BVC_REL: OpCode = OpCode {
     op: 0x50,
     name: "BVC",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const BVS_REL: OpCode = OpCode {
     op: 0x70,
     name: "BVS",
     n: 2,
     cyc: 2,
+    mode: Mode::Rel,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const CLC: OpCode = OpCode {
     op: 0x18,
     name: "CLC",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: C,
 };
 pub const CLD: OpCode = OpCode {
     op: 0xD8,
     name: "CLD",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: D,
 };
 pub const CLI: OpCode = OpCode {
     op: 0x58,
     name: "CLI",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: I,
 };
 pub const CLV: OpCode = OpCode {
     op: 0xB8,
     name: "CLV",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: O,
 };
 pub const CMP_ABS: OpCode = OpCode {
     op: 0xCD,
     name: "CMP",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CMP_ABS_X: OpCode = OpCode {
     op: 0xDD,
     name: "CMP",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZC,
 };
 pub const CMP_ABS_Y: OpCode = OpCode {
     op: 0xD9,
     name: "CMP",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZC,
 };
 pub const CMP_IMM: OpCode = OpCode {
     op: 0xC9,
     name: "CMP",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CMP_IND_Y: OpCode = OpCode {
     op: 0xD1,
     name: "CMP",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZC,
 };
 pub const CMP_X_IND: OpCode = OpCode {
     op: 0xC1,
     name: "CMP",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CMP_ZP: OpCode = OpCode {
     op: 0xC5,
     name: "CMP",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CMP_ZP_X: OpCode = OpCode {
     op: 0xD5,
     name: "CMP",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPX_ABS: OpCode = OpCode {
     op: 0xEC,
     name: "CPX",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPX_IMM: OpCode = OpCode {
     op: 0xE0,
     name: "CPX",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPX_ZP: OpCode = OpCode {
     op: 0xE4,
     name: "CPX",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPY_ABS: OpCode = OpCode {
     op: 0xCC,
     name: "CPY",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPY_IMM: OpCode = OpCode {
     op: 0xC0,
     name: "CPY",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const CPY_ZP: OpCode = OpCode {
     op: 0xC4,
     name: "CPY",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const DEC_ABS: OpCode = OpCode {
     op: 0xCE,
     name: "DEC",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const DEC_ABS_X: OpCode = OpCode {
     op: 0xDE,
     name: "DEC",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const DEC_ZP: OpCode = OpCode {
     op: 0xC6,
     name: "DEC",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const DEC_ZP_X: OpCode = OpCode {
     op: 0xD6,
     name: "DEC",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const DEX: OpCode = OpCode {
     op: 0xCA,
     name: "DEX",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const DEY: OpCode = OpCode {
     op: 0x88,
     name: "DEY",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const EOR_ABS: OpCode = OpCode {
     op: 0x4D,
     name: "EOR",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const EOR_ABS_X: OpCode = OpCode {
     op: 0x5D,
     name: "EOR",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const EOR_ABS_Y: OpCode = OpCode {
     op: 0x59,
     name: "EOR",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const EOR_IMM: OpCode = OpCode {
     op: 0x49,
     name: "EOR",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const EOR_IND_Y: OpCode = OpCode {
     op: 0x51,
     name: "EOR",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const EOR_X_IND: OpCode = OpCode {
     op: 0x41,
     name: "EOR",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const EOR_ZP: OpCode = OpCode {
     op: 0x45,
     name: "EOR",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const EOR_ZP_X: OpCode = OpCode {
     op: 0x55,
     name: "EOR",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INC_ABS: OpCode = OpCode {
     op: 0xEE,
     name: "INC",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INC_ABS_X: OpCode = OpCode {
     op: 0xFE,
     name: "INC",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INC_ZP: OpCode = OpCode {
     op: 0xE6,
     name: "INC",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INC_ZP_X: OpCode = OpCode {
     op: 0xF6,
     name: "INC",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INX: OpCode = OpCode {
     op: 0xE8,
     name: "INX",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const INY: OpCode = OpCode {
     op: 0xC8,
     name: "INY",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const JMP_ABS: OpCode = OpCode {
     op: 0x4C,
     name: "JMP",
     n: 3,
     cyc: 3,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const JMP_IND: OpCode = OpCode {
     op: 0x6C,
     name: "JMP",
     n: 3,
     cyc: 5,
+    mode: Mode::Ind,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const JSR_ABS: OpCode = OpCode {
     op: 0x20,
     name: "JSR",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const LDA_ABS: OpCode = OpCode {
     op: 0xAD,
     name: "LDA",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDA_ABS_X: OpCode = OpCode {
     op: 0xBD,
     name: "LDA",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const LDA_ABS_Y: OpCode = OpCode {
     op: 0xB9,
     name: "LDA",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const LDA_IMM: OpCode = OpCode {
     op: 0xA9,
     name: "LDA",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDA_IND_Y: OpCode = OpCode {
     op: 0xB1,
     name: "LDA",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const LDA_X_IND: OpCode = OpCode {
     op: 0xA1,
     name: "LDA",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDA_ZP: OpCode = OpCode {
     op: 0xA5,
     name: "LDA",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDA_ZP_X: OpCode = OpCode {
     op: 0xB5,
     name: "LDA",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDX_ABS: OpCode = OpCode {
     op: 0xAE,
     name: "LDX",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDX_ABS_Y: OpCode = OpCode {
     op: 0xBE,
     name: "LDX",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const LDX_IMM: OpCode = OpCode {
     op: 0xA2,
     name: "LDX",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDX_ZP: OpCode = OpCode {
     op: 0xA6,
     name: "LDX",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDX_ZP_Y: OpCode = OpCode {
     op: 0xB6,
     name: "LDX",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpY,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDY_ABS: OpCode = OpCode {
     op: 0xAC,
     name: "LDY",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDY_ABS_X: OpCode = OpCode {
     op: 0xBC,
     name: "LDY",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const LDY_IMM: OpCode = OpCode {
     op: 0xA0,
     name: "LDY",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDY_ZP: OpCode = OpCode {
     op: 0xA4,
     name: "LDY",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LDY_ZP_X: OpCode = OpCode {
     op: 0xB4,
     name: "LDY",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const LSR_A: OpCode = OpCode {
     op: 0x4A,
     name: "LSR",
     n: 1,
     cyc: 2,
+    mode: Mode::Acc,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const LSR_ABS: OpCode = OpCode {
     op: 0x4E,
     name: "LSR",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const LSR_ABS_X: OpCode = OpCode {
     op: 0x5E,
     name: "LSR",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const LSR_ZP: OpCode = OpCode {
     op: 0x46,
     name: "LSR",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const LSR_ZP_X: OpCode = OpCode {
     op: 0x56,
     name: "LSR",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const NOP: OpCode = OpCode {
     op: 0xEA,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const ORA_ABS: OpCode = OpCode {
     op: 0x0D,
     name: "ORA",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const ORA_ABS_X: OpCode = OpCode {
     op: 0x1D,
     name: "ORA",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const ORA_ABS_Y: OpCode = OpCode {
     op: 0x19,
     name: "ORA",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const ORA_IMM: OpCode = OpCode {
     op: 0x09,
     name: "ORA",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const ORA_IND_Y: OpCode = OpCode {
     op: 0x11,
     name: "ORA",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NZ,
 };
 pub const ORA_X_IND: OpCode = OpCode {
     op: 0x01,
     name: "ORA",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const ORA_ZP: OpCode = OpCode {
     op: 0x05,
     name: "ORA",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const ORA_ZP_X: OpCode = OpCode {
     op: 0x15,
     name: "ORA",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const PHA: OpCode = OpCode {
     op: 0x48,
     name: "PHA",
     n: 1,
     cyc: 3,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const PHP: OpCode = OpCode {
     op: 0x08,
     name: "PHP",
     n: 1,
     cyc: 3,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const PLA: OpCode = OpCode {
     op: 0x68,
     name: "PLA",
     n: 1,
     cyc: 4,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const PLP: OpCode = OpCode {
     op: 0x28,
     name: "PLP",
     n: 1,
     cyc: 4,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVDIZC,
 };
 pub const ROL_A: OpCode = OpCode {
     op: 0x2A,
     name: "ROL",
     n: 1,
     cyc: 2,
+    mode: Mode::Acc,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROL_ABS: OpCode = OpCode {
     op: 0x2E,
     name: "ROL",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROL_ABS_X: OpCode = OpCode {
     op: 0x3E,
     name: "ROL",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROL_ZP: OpCode = OpCode {
     op: 0x26,
     name: "ROL",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROL_ZP_X: OpCode = OpCode {
     op: 0x36,
     name: "ROL",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROR_A: OpCode = OpCode {
     op: 0x6A,
     name: "ROR",
     n: 1,
     cyc: 2,
+    mode: Mode::Acc,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROR_ABS: OpCode = OpCode {
     op: 0x6E,
     name: "ROR",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROR_ABS_X: OpCode = OpCode {
     op: 0x7E,
     name: "ROR",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROR_ZP: OpCode = OpCode {
     op: 0x66,
     name: "ROR",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const ROR_ZP_X: OpCode = OpCode {
     op: 0x76,
     name: "ROR",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZC,
 };
 pub const RTI: OpCode = OpCode {
     op: 0x40,
     name: "RTI",
     n: 1,
     cyc: 6,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVDIZC,
 };
 pub const RTS: OpCode = OpCode {
     op: 0x60,
     name: "RTS",
     n: 1,
     cyc: 6,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SBC_ABS: OpCode = OpCode {
     op: 0xED,
     name: "SBC",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SBC_ABS_X: OpCode = OpCode {
     op: 0xFD,
     name: "SBC",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const SBC_ABS_Y: OpCode = OpCode {
     op: 0xF9,
     name: "SBC",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const SBC_IMM: OpCode = OpCode {
     op: 0xE9,
     name: "SBC",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SBC_IND_Y: OpCode = OpCode {
     op: 0xF1,
     name: "SBC",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: true,
+    flags: NVZC,
 };
 pub const SBC_X_IND: OpCode = OpCode {
     op: 0xE1,
     name: "SBC",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SBC_ZP: OpCode = OpCode {
     op: 0xE5,
     name: "SBC",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SBC_ZP_X: OpCode = OpCode {
     op: 0xF5,
     name: "SBC",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SEC: OpCode = OpCode {
     op: 0x38,
     name: "SEC",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: C,
 };
 pub const SED: OpCode = OpCode {
     op: 0xF8,
     name: "SED",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: D,
 };
 pub const SEI: OpCode = OpCode {
     op: 0x78,
     name: "SEI",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: I,
 };
 pub const STA_ABS: OpCode = OpCode {
     op: 0x8D,
     name: "STA",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_ABS_X: OpCode = OpCode {
     op: 0x9D,
     name: "STA",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_ABS_Y: OpCode = OpCode {
     op: 0x99,
     name: "STA",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsY,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_IND_Y: OpCode = OpCode {
     op: 0x91,
     name: "STA",
     n: 2,
     cyc: 6,
+    mode: Mode::IndY,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_X_IND: OpCode = OpCode {
     op: 0x81,
     name: "STA",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_ZP: OpCode = OpCode {
     op: 0x85,
     name: "STA",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STA_ZP_X: OpCode = OpCode {
     op: 0x95,
     name: "STA",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STX_ABS: OpCode = OpCode {
     op: 0x8E,
     name: "STX",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STX_ZP: OpCode = OpCode {
     op: 0x86,
     name: "STX",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STX_ZP_Y: OpCode = OpCode {
     op: 0x96,
     name: "STX",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpY,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STY_ABS: OpCode = OpCode {
     op: 0x8C,
     name: "STY",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STY_ZP: OpCode = OpCode {
     op: 0x84,
     name: "STY",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const STY_ZP_X: OpCode = OpCode {
     op: 0x94,
     name: "STY",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const TAX: OpCode = OpCode {
     op: 0xAA,
     name: "TAX",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const TAY: OpCode = OpCode {
     op: 0xA8,
     name: "TAY",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const TSX: OpCode = OpCode {
     op: 0xBA,
     name: "TSX",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const TXA: OpCode = OpCode {
     op: 0x8A,
     name: "TXA",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 pub const TXS: OpCode = OpCode {
     op: 0x9A,
     name: "TXS",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const TYA: OpCode = OpCode {
     op: 0x98,
     name: "TYA",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Official,
+    penalty: false,
+    flags: NZ,
 };
 
 // NES Unofficial Opcodes
@@ -944,516 +1592,860 @@ pub const ALR_IMM: OpCode = OpCode {
     name: "ALR",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const ANC_2B_IMM: OpCode = OpCode {
     op: 0x2B,
     name: "ANC",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const ANC_IMM: OpCode = OpCode {
     op: 0x0B,
     name: "ANC",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const ARR_IMM: OpCode = OpCode {
     op: 0x6B,
     name: "ARR",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const AXS_IMM: OpCode = OpCode {
     op: 0xCB,
     name: "AXS",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_ABS: OpCode = OpCode {
     op: 0xCF,
     name: "DCP",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_ABS_X: OpCode = OpCode {
     op: 0xDF,
     name: "DCP",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_ABS_Y: OpCode = OpCode {
     op: 0xDB,
     name: "DCP",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_IND_Y: OpCode = OpCode {
     op: 0xD3,
     name: "DCP",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_X_IND: OpCode = OpCode {
     op: 0xC3,
     name: "DCP",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_ZP: OpCode = OpCode {
     op: 0xC7,
     name: "DCP",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const DCP_ZP_X: OpCode = OpCode {
     op: 0xD7,
     name: "DCP",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const IGN_04_ZP: OpCode = OpCode {
     op: 0x04,
     name: "IGN",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_14_ZP_X: OpCode = OpCode {
     op: 0x14,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_1C_ABS_X: OpCode = OpCode {
     op: 0x1C,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const IGN_34_ZP_X: OpCode = OpCode {
     op: 0x34,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_3C_ABS_X: OpCode = OpCode {
     op: 0x3C,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const IGN_44_ZP: OpCode = OpCode {
     op: 0x44,
     name: "IGN",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_54_ZP_X: OpCode = OpCode {
     op: 0x54,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_5C_ABS_X: OpCode = OpCode {
     op: 0x5C,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const IGN_64_ZP: OpCode = OpCode {
     op: 0x64,
     name: "IGN",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_74_ZP_X: OpCode = OpCode {
     op: 0x74,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_7C_ABS_X: OpCode = OpCode {
     op: 0x7C,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const IGN_ABS: OpCode = OpCode {
     op: 0x0C,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_D4_ZP_X: OpCode = OpCode {
     op: 0xD4,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_DC_ABS_X: OpCode = OpCode {
     op: 0xDC,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const IGN_F4_ZP_X: OpCode = OpCode {
     op: 0xF4,
     name: "IGN",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const IGN_FC_ABS_X: OpCode = OpCode {
     op: 0xFC,
     name: "IGN",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NO_FLAGS,
 };
 pub const ISC_ABS: OpCode = OpCode {
     op: 0xEF,
     name: "ISC",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_ABS_X: OpCode = OpCode {
     op: 0xFF,
     name: "ISC",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_ABS_Y: OpCode = OpCode {
     op: 0xFB,
     name: "ISC",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_IND_Y: OpCode = OpCode {
     op: 0xF3,
     name: "ISC",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_X_IND: OpCode = OpCode {
     op: 0xE3,
     name: "ISC",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_ZP: OpCode = OpCode {
     op: 0xE7,
     name: "ISC",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const ISC_ZP_X: OpCode = OpCode {
     op: 0xF7,
     name: "ISC",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const LAX_ABS: OpCode = OpCode {
     op: 0xAF,
     name: "LAX",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZ,
 };
 pub const LAX_ABS_Y: OpCode = OpCode {
     op: 0xBF,
     name: "LAX",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NZ,
 };
 pub const LAX_IND_Y: OpCode = OpCode {
     op: 0xB3,
     name: "LAX",
     n: 2,
     cyc: 5,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NZ,
 };
 pub const LAX_X_IND: OpCode = OpCode {
     op: 0xA3,
     name: "LAX",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZ,
 };
 pub const LAX_ZP: OpCode = OpCode {
     op: 0xA7,
     name: "LAX",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZ,
 };
 pub const LAX_ZP_Y: OpCode = OpCode {
     op: 0xB7,
     name: "LAX",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZ,
 };
 pub const NOP_1A: OpCode = OpCode {
     op: 0x1A,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const NOP_3A: OpCode = OpCode {
     op: 0x3A,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const NOP_5A: OpCode = OpCode {
     op: 0x5A,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const NOP_7A: OpCode = OpCode {
     op: 0x7A,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const NOP_DA: OpCode = OpCode {
     op: 0xDA,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const NOP_FA: OpCode = OpCode {
     op: 0xFA,
     name: "NOP",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const RLA_ABS: OpCode = OpCode {
     op: 0x2F,
     name: "RLA",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_ABS_X: OpCode = OpCode {
     op: 0x3F,
     name: "RLA",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_ABS_Y: OpCode = OpCode {
     op: 0x3B,
     name: "RLA",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_IND_Y: OpCode = OpCode {
     op: 0x33,
     name: "RLA",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_X_IND: OpCode = OpCode {
     op: 0x23,
     name: "RLA",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_ZP: OpCode = OpCode {
     op: 0x27,
     name: "RLA",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RLA_ZP_X: OpCode = OpCode {
     op: 0x37,
     name: "RLA",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const RRA_ABS: OpCode = OpCode {
     op: 0x6F,
     name: "RRA",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_ABS_X: OpCode = OpCode {
     op: 0x7F,
     name: "RRA",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_ABS_Y: OpCode = OpCode {
     op: 0x7B,
     name: "RRA",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_IND_Y: OpCode = OpCode {
     op: 0x73,
     name: "RRA",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_X_IND: OpCode = OpCode {
     op: 0x63,
     name: "RRA",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_ZP: OpCode = OpCode {
     op: 0x67,
     name: "RRA",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const RRA_ZP_X: OpCode = OpCode {
     op: 0x77,
     name: "RRA",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SAX_ABS: OpCode = OpCode {
     op: 0x8F,
     name: "SAX",
     n: 3,
     cyc: 4,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SAX_X_IND: OpCode = OpCode {
     op: 0x83,
     name: "SAX",
     n: 2,
     cyc: 6,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SAX_ZP: OpCode = OpCode {
     op: 0x87,
     name: "SAX",
     n: 2,
     cyc: 3,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SAX_ZP_Y: OpCode = OpCode {
     op: 0x97,
     name: "SAX",
     n: 2,
     cyc: 4,
+    mode: Mode::ZpY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SBC_EB_IMM: OpCode = OpCode {
     op: 0xEB,
     name: "SBC",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NVZC,
 };
 pub const SKB_80_IMM: OpCode = OpCode {
     op: 0x80,
     name: "SKB",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SKB_82_IMM: OpCode = OpCode {
     op: 0x82,
     name: "SKB",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SKB_89_IMM: OpCode = OpCode {
     op: 0x89,
     name: "SKB",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SKB_C2_IMM: OpCode = OpCode {
     op: 0xC2,
     name: "SKB",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SKB_E2_IMM: OpCode = OpCode {
     op: 0xE2,
     name: "SKB",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SLO_ABS: OpCode = OpCode {
     op: 0x0F,
     name: "SLO",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_ABS_X: OpCode = OpCode {
     op: 0x1F,
     name: "SLO",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_ABS_Y: OpCode = OpCode {
     op: 0x1B,
     name: "SLO",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_IND_Y: OpCode = OpCode {
     op: 0x13,
     name: "SLO",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_X_IND: OpCode = OpCode {
     op: 0x03,
     name: "SLO",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_ZP: OpCode = OpCode {
     op: 0x07,
     name: "SLO",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SLO_ZP_X: OpCode = OpCode {
     op: 0x17,
     name: "SLO",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_ABS: OpCode = OpCode {
     op: 0x4F,
     name: "SRE",
     n: 3,
     cyc: 6,
+    mode: Mode::Abs,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_ABS_X: OpCode = OpCode {
     op: 0x5F,
     name: "SRE",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_ABS_Y: OpCode = OpCode {
     op: 0x5B,
     name: "SRE",
     n: 3,
     cyc: 7,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_IND_Y: OpCode = OpCode {
     op: 0x53,
     name: "SRE",
     n: 2,
     cyc: 8,
+    mode: Mode::IndY,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_X_IND: OpCode = OpCode {
     op: 0x43,
     name: "SRE",
     n: 2,
     cyc: 8,
+    mode: Mode::IndX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_ZP: OpCode = OpCode {
     op: 0x47,
     name: "SRE",
     n: 2,
     cyc: 5,
+    mode: Mode::Zp,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const SRE_ZP_X: OpCode = OpCode {
     op: 0x57,
     name: "SRE",
     n: 2,
     cyc: 6,
+    mode: Mode::ZpX,
+    kind: Kind::Unofficial,
+    penalty: false,
+    flags: NZC,
 };
 pub const XAA_IMM: OpCode = OpCode {
     op: 0x8B,
     name: "XAA",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NZ,
 };
 
 // Other illegal opcodes
@@ -1462,42 +2454,70 @@ pub const LAS_ABS_Y: OpCode = OpCode {
     name: "LAS",
     n: 3,
     cyc: 4,
+    mode: Mode::AbsY,
+    kind: Kind::Unofficial,
+    penalty: true,
+    flags: NZ,
 };
 pub const LXA_IMM: OpCode = OpCode {
     op: 0xAB,
     name: "LXA",
     n: 2,
     cyc: 2,
+    mode: Mode::Immd,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NZ,
 };
 pub const SHA_ABS_Y: OpCode = OpCode {
     op: 0x9F,
     name: "SHA",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsY,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SHA_IND_Y: OpCode = OpCode {
     op: 0x93,
     name: "SHA",
     n: 2,
     cyc: 6,
+    mode: Mode::IndY,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SHX_ABS_Y: OpCode = OpCode {
     op: 0x9E,
     name: "SHX",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsY,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const SHY_ABS_X: OpCode = OpCode {
     op: 0x9C,
     name: "SHY",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsX,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const TAS_ABS_Y: OpCode = OpCode {
     op: 0x9B,
     name: "TAS",
     n: 3,
     cyc: 5,
+    mode: Mode::AbsY,
+    kind: Kind::Unstable,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 
 // KIL
@@ -1506,70 +2526,1821 @@ pub const KIL: OpCode = OpCode {
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_12: OpCode = OpCode {
     op: 0x12,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_22: OpCode = OpCode {
     op: 0x22,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_32: OpCode = OpCode {
     op: 0x32,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_42: OpCode = OpCode {
     op: 0x42,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_52: OpCode = OpCode {
     op: 0x52,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_62: OpCode = OpCode {
     op: 0x62,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_72: OpCode = OpCode {
     op: 0x72,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_92: OpCode = OpCode {
     op: 0x92,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_B2: OpCode = OpCode {
     op: 0xB2,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_D2: OpCode = OpCode {
     op: 0xD2,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
 pub const KIL_F2: OpCode = OpCode {
     op: 0xF2,
     name: "KIL",
     n: 1,
     cyc: 2,
+    mode: Mode::Impl,
+    kind: Kind::Jam,
+    penalty: false,
+    flags: NO_FLAGS,
 };
+
+// Decode table
+/// All opcode constants. The array length makes the compiler reject a
+/// missing constant and [`OPCODES`] rejects two constants sharing one byte,
+/// so every byte 0x00-0xFF is decoded exactly once.
+const ALL: [OpCode; 256] = [
+    ADC_ABS,
+    ADC_ABS_X,
+    ADC_ABS_Y,
+    ADC_IMM,
+    ADC_IND_Y,
+    ADC_X_IND,
+    ADC_ZP,
+    ADC_ZP_X,
+    AND_ABS,
+    AND_ABS_X,
+    AND_ABS_Y,
+    AND_IMM,
+    AND_IND_Y,
+    AND_X_IND,
+    AND_ZP,
+    AND_ZP_X,
+    ASL_A,
+    ASL_ABS,
+    ASL_ABS_X,
+    ASL_ZP,
+    ASL_ZP_X,
+    BCC_REL,
+    BCS_REL,
+    BEQ_REL,
+    BIT_ABS,
+    BIT_ZP,
+    BMI_REL,
+    BNE_REL,
+    BPL_REL,
+    BRK,
+    BVC_REL,
+    BVS_REL,
+    CLC,
+    CLD,
+    CLI,
+    CLV,
+    CMP_ABS,
+    CMP_ABS_X,
+    CMP_ABS_Y,
+    CMP_IMM,
+    CMP_IND_Y,
+    CMP_X_IND,
+    CMP_ZP,
+    CMP_ZP_X,
+    CPX_ABS,
+    CPX_IMM,
+    CPX_ZP,
+    CPY_ABS,
+    CPY_IMM,
+    CPY_ZP,
+    DEC_ABS,
+    DEC_ABS_X,
+    DEC_ZP,
+    DEC_ZP_X,
+    DEX,
+    DEY,
+    EOR_ABS,
+    EOR_ABS_X,
+    EOR_ABS_Y,
+    EOR_IMM,
+    EOR_IND_Y,
+    EOR_X_IND,
+    EOR_ZP,
+    EOR_ZP_X,
+    INC_ABS,
+    INC_ABS_X,
+    INC_ZP,
+    INC_ZP_X,
+    INX,
+    INY,
+    JMP_ABS,
+    JMP_IND,
+    JSR_ABS,
+    LDA_ABS,
+    LDA_ABS_X,
+    LDA_ABS_Y,
+    LDA_IMM,
+    LDA_IND_Y,
+    LDA_X_IND,
+    LDA_ZP,
+    LDA_ZP_X,
+    LDX_ABS,
+    LDX_ABS_Y,
+    LDX_IMM,
+    LDX_ZP,
+    LDX_ZP_Y,
+    LDY_ABS,
+    LDY_ABS_X,
+    LDY_IMM,
+    LDY_ZP,
+    LDY_ZP_X,
+    LSR_A,
+    LSR_ABS,
+    LSR_ABS_X,
+    LSR_ZP,
+    LSR_ZP_X,
+    NOP,
+    ORA_ABS,
+    ORA_ABS_X,
+    ORA_ABS_Y,
+    ORA_IMM,
+    ORA_IND_Y,
+    ORA_X_IND,
+    ORA_ZP,
+    ORA_ZP_X,
+    PHA,
+    PHP,
+    PLA,
+    PLP,
+    ROL_A,
+    ROL_ABS,
+    ROL_ABS_X,
+    ROL_ZP,
+    ROL_ZP_X,
+    ROR_A,
+    ROR_ABS,
+    ROR_ABS_X,
+    ROR_ZP,
+    ROR_ZP_X,
+    RTI,
+    RTS,
+    SBC_ABS,
+    SBC_ABS_X,
+    SBC_ABS_Y,
+    SBC_IMM,
+    SBC_IND_Y,
+    SBC_X_IND,
+    SBC_ZP,
+    SBC_ZP_X,
+    SEC,
+    SED,
+    SEI,
+    STA_ABS,
+    STA_ABS_X,
+    STA_ABS_Y,
+    STA_IND_Y,
+    STA_X_IND,
+    STA_ZP,
+    STA_ZP_X,
+    STX_ABS,
+    STX_ZP,
+    STX_ZP_Y,
+    STY_ABS,
+    STY_ZP,
+    STY_ZP_X,
+    TAX,
+    TAY,
+    TSX,
+    TXA,
+    TXS,
+    TYA,
+    ALR_IMM,
+    ANC_2B_IMM,
+    ANC_IMM,
+    ARR_IMM,
+    AXS_IMM,
+    DCP_ABS,
+    DCP_ABS_X,
+    DCP_ABS_Y,
+    DCP_IND_Y,
+    DCP_X_IND,
+    DCP_ZP,
+    DCP_ZP_X,
+    IGN_04_ZP,
+    IGN_14_ZP_X,
+    IGN_1C_ABS_X,
+    IGN_34_ZP_X,
+    IGN_3C_ABS_X,
+    IGN_44_ZP,
+    IGN_54_ZP_X,
+    IGN_5C_ABS_X,
+    IGN_64_ZP,
+    IGN_74_ZP_X,
+    IGN_7C_ABS_X,
+    IGN_ABS,
+    IGN_D4_ZP_X,
+    IGN_DC_ABS_X,
+    IGN_F4_ZP_X,
+    IGN_FC_ABS_X,
+    ISC_ABS,
+    ISC_ABS_X,
+    ISC_ABS_Y,
+    ISC_IND_Y,
+    ISC_X_IND,
+    ISC_ZP,
+    ISC_ZP_X,
+    LAX_ABS,
+    LAX_ABS_Y,
+    LAX_IND_Y,
+    LAX_X_IND,
+    LAX_ZP,
+    LAX_ZP_Y,
+    NOP_1A,
+    NOP_3A,
+    NOP_5A,
+    NOP_7A,
+    NOP_DA,
+    NOP_FA,
+    RLA_ABS,
+    RLA_ABS_X,
+    RLA_ABS_Y,
+    RLA_IND_Y,
+    RLA_X_IND,
+    RLA_ZP,
+    RLA_ZP_X,
+    RRA_ABS,
+    RRA_ABS_X,
+    RRA_ABS_Y,
+    RRA_IND_Y,
+    RRA_X_IND,
+    RRA_ZP,
+    RRA_ZP_X,
+    SAX_ABS,
+    SAX_X_IND,
+    SAX_ZP,
+    SAX_ZP_Y,
+    SBC_EB_IMM,
+    SKB_80_IMM,
+    SKB_82_IMM,
+    SKB_89_IMM,
+    SKB_C2_IMM,
+    SKB_E2_IMM,
+    SLO_ABS,
+    SLO_ABS_X,
+    SLO_ABS_Y,
+    SLO_IND_Y,
+    SLO_X_IND,
+    SLO_ZP,
+    SLO_ZP_X,
+    SRE_ABS,
+    SRE_ABS_X,
+    SRE_ABS_Y,
+    SRE_IND_Y,
+    SRE_X_IND,
+    SRE_ZP,
+    SRE_ZP_X,
+    XAA_IMM,
+    LAS_ABS_Y,
+    LXA_IMM,
+    SHA_ABS_Y,
+    SHA_IND_Y,
+    SHX_ABS_Y,
+    SHY_ABS_X,
+    TAS_ABS_Y,
+    KIL,
+    KIL_12,
+    KIL_22,
+    KIL_32,
+    KIL_42,
+    KIL_52,
+    KIL_62,
+    KIL_72,
+    KIL_92,
+    KIL_B2,
+    KIL_D2,
+    KIL_F2,
+];
+
+/// OpCode lookup table indexed by opcode byte
+pub static OPCODES: [OpCode; 256] = decode_table(&ALL);
+
+/// decode an opcode byte
+pub fn decode(op: u8) -> &'static OpCode {
+    &OPCODES[op as usize]
+}
+
+// place every OpCode at the index of its byte, evaluated at compile time
+const fn decode_table(list: &[OpCode; 256]) -> [OpCode; 256] {
+    let mut table = [KIL; 256];
+    let mut seen = [false; 256];
+    let mut i = 0;
+    while i < list.len() {
+        let op = list[i].op as usize;
+        if seen[op] {
+            panic!("two OpCode constants claim the same opcode byte");
+        }
+        seen[op] = true;
+        table[op] = list[i];
+        i += 1;
+    }
+    table
+}
+
+/// 65C02 OpCodes
+///
+/// The CMOS 65C02 keeps the official NMOS opcodes, with the timing changes
+/// redefined here, adds new instructions (including the Rockwell/WDC bit
+/// instructions) in the unofficial slots and turns the remaining slots into
+/// reserved NOPs. `$CB` and `$DB` are the Rockwell NOPs rather than the WDC
+/// WAI and STP.
+///
+/// Reference:
+/// - <http://www.6502.org/tutorials/65c02opcodes.html>
+/// - <https://www.masswerk.at/6502/6502_instruction_set.html>
+pub mod cmos {
+    use super::*;
+
+    // NMOS opcodes with 65C02 timing
+    pub const ASL_ABS_X: OpCode = OpCode {
+        op: 0x1E,
+        name: "ASL",
+        n: 3,
+        cyc: 6,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NZC,
+    };
+    pub const JMP_IND: OpCode = OpCode {
+        op: 0x6C,
+        name: "JMP",
+        n: 3,
+        cyc: 6,
+        mode: Mode::Ind,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const LSR_ABS_X: OpCode = OpCode {
+        op: 0x5E,
+        name: "LSR",
+        n: 3,
+        cyc: 6,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NZC,
+    };
+    pub const ROL_ABS_X: OpCode = OpCode {
+        op: 0x3E,
+        name: "ROL",
+        n: 3,
+        cyc: 6,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NZC,
+    };
+    pub const ROR_ABS_X: OpCode = OpCode {
+        op: 0x7E,
+        name: "ROR",
+        n: 3,
+        cyc: 6,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NZC,
+    };
+
+    // 65C02 opcodes
+    pub const ADC_ZP_IND: OpCode = OpCode {
+        op: 0x72,
+        name: "ADC",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NVZC,
+    };
+    pub const AND_ZP_IND: OpCode = OpCode {
+        op: 0x32,
+        name: "AND",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const BBR0_ZP_REL: OpCode = OpCode {
+        op: 0x0F,
+        name: "BBR0",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR1_ZP_REL: OpCode = OpCode {
+        op: 0x1F,
+        name: "BBR1",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR2_ZP_REL: OpCode = OpCode {
+        op: 0x2F,
+        name: "BBR2",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR3_ZP_REL: OpCode = OpCode {
+        op: 0x3F,
+        name: "BBR3",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR4_ZP_REL: OpCode = OpCode {
+        op: 0x4F,
+        name: "BBR4",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR5_ZP_REL: OpCode = OpCode {
+        op: 0x5F,
+        name: "BBR5",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR6_ZP_REL: OpCode = OpCode {
+        op: 0x6F,
+        name: "BBR6",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBR7_ZP_REL: OpCode = OpCode {
+        op: 0x7F,
+        name: "BBR7",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS0_ZP_REL: OpCode = OpCode {
+        op: 0x8F,
+        name: "BBS0",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS1_ZP_REL: OpCode = OpCode {
+        op: 0x9F,
+        name: "BBS1",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS2_ZP_REL: OpCode = OpCode {
+        op: 0xAF,
+        name: "BBS2",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS3_ZP_REL: OpCode = OpCode {
+        op: 0xBF,
+        name: "BBS3",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS4_ZP_REL: OpCode = OpCode {
+        op: 0xCF,
+        name: "BBS4",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS5_ZP_REL: OpCode = OpCode {
+        op: 0xDF,
+        name: "BBS5",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS6_ZP_REL: OpCode = OpCode {
+        op: 0xEF,
+        name: "BBS6",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BBS7_ZP_REL: OpCode = OpCode {
+        op: 0xFF,
+        name: "BBS7",
+        n: 3,
+        cyc: 5,
+        mode: Mode::ZpRel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const BIT_ABS_X: OpCode = OpCode {
+        op: 0x3C,
+        name: "BIT",
+        n: 3,
+        cyc: 4,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NVZ,
+    };
+    pub const BIT_IMM: OpCode = OpCode {
+        op: 0x89,
+        name: "BIT",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: Z,
+    };
+    pub const BIT_ZP_X: OpCode = OpCode {
+        op: 0x34,
+        name: "BIT",
+        n: 2,
+        cyc: 4,
+        mode: Mode::ZpX,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NVZ,
+    };
+    pub const BRA_REL: OpCode = OpCode {
+        op: 0x80,
+        name: "BRA",
+        n: 2,
+        cyc: 3,
+        mode: Mode::Rel,
+        kind: Kind::Official,
+        penalty: true,
+        flags: NO_FLAGS,
+    };
+    pub const CMP_ZP_IND: OpCode = OpCode {
+        op: 0xD2,
+        name: "CMP",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZC,
+    };
+    pub const DEC_A: OpCode = OpCode {
+        op: 0x3A,
+        name: "DEC",
+        n: 1,
+        cyc: 2,
+        mode: Mode::Acc,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const EOR_ZP_IND: OpCode = OpCode {
+        op: 0x52,
+        name: "EOR",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const INC_A: OpCode = OpCode {
+        op: 0x1A,
+        name: "INC",
+        n: 1,
+        cyc: 2,
+        mode: Mode::Acc,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const JMP_ABS_X_IND: OpCode = OpCode {
+        op: 0x7C,
+        name: "JMP",
+        n: 3,
+        cyc: 6,
+        mode: Mode::AbsXInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const LDA_ZP_IND: OpCode = OpCode {
+        op: 0xB2,
+        name: "LDA",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const ORA_ZP_IND: OpCode = OpCode {
+        op: 0x12,
+        name: "ORA",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const PHX: OpCode = OpCode {
+        op: 0xDA,
+        name: "PHX",
+        n: 1,
+        cyc: 3,
+        mode: Mode::Impl,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const PHY: OpCode = OpCode {
+        op: 0x5A,
+        name: "PHY",
+        n: 1,
+        cyc: 3,
+        mode: Mode::Impl,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const PLX: OpCode = OpCode {
+        op: 0xFA,
+        name: "PLX",
+        n: 1,
+        cyc: 4,
+        mode: Mode::Impl,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const PLY: OpCode = OpCode {
+        op: 0x7A,
+        name: "PLY",
+        n: 1,
+        cyc: 4,
+        mode: Mode::Impl,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NZ,
+    };
+    pub const RMB0_ZP: OpCode = OpCode {
+        op: 0x07,
+        name: "RMB0",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB1_ZP: OpCode = OpCode {
+        op: 0x17,
+        name: "RMB1",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB2_ZP: OpCode = OpCode {
+        op: 0x27,
+        name: "RMB2",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB3_ZP: OpCode = OpCode {
+        op: 0x37,
+        name: "RMB3",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB4_ZP: OpCode = OpCode {
+        op: 0x47,
+        name: "RMB4",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB5_ZP: OpCode = OpCode {
+        op: 0x57,
+        name: "RMB5",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB6_ZP: OpCode = OpCode {
+        op: 0x67,
+        name: "RMB6",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const RMB7_ZP: OpCode = OpCode {
+        op: 0x77,
+        name: "RMB7",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SBC_ZP_IND: OpCode = OpCode {
+        op: 0xF2,
+        name: "SBC",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NVZC,
+    };
+    pub const SMB0_ZP: OpCode = OpCode {
+        op: 0x87,
+        name: "SMB0",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB1_ZP: OpCode = OpCode {
+        op: 0x97,
+        name: "SMB1",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB2_ZP: OpCode = OpCode {
+        op: 0xA7,
+        name: "SMB2",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB3_ZP: OpCode = OpCode {
+        op: 0xB7,
+        name: "SMB3",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB4_ZP: OpCode = OpCode {
+        op: 0xC7,
+        name: "SMB4",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB5_ZP: OpCode = OpCode {
+        op: 0xD7,
+        name: "SMB5",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB6_ZP: OpCode = OpCode {
+        op: 0xE7,
+        name: "SMB6",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const SMB7_ZP: OpCode = OpCode {
+        op: 0xF7,
+        name: "SMB7",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const STA_ZP_IND: OpCode = OpCode {
+        op: 0x92,
+        name: "STA",
+        n: 2,
+        cyc: 5,
+        mode: Mode::ZpInd,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const STZ_ABS: OpCode = OpCode {
+        op: 0x9C,
+        name: "STZ",
+        n: 3,
+        cyc: 4,
+        mode: Mode::Abs,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const STZ_ABS_X: OpCode = OpCode {
+        op: 0x9E,
+        name: "STZ",
+        n: 3,
+        cyc: 5,
+        mode: Mode::AbsX,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const STZ_ZP: OpCode = OpCode {
+        op: 0x64,
+        name: "STZ",
+        n: 2,
+        cyc: 3,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const STZ_ZP_X: OpCode = OpCode {
+        op: 0x74,
+        name: "STZ",
+        n: 2,
+        cyc: 4,
+        mode: Mode::ZpX,
+        kind: Kind::Official,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const TRB_ABS: OpCode = OpCode {
+        op: 0x1C,
+        name: "TRB",
+        n: 3,
+        cyc: 6,
+        mode: Mode::Abs,
+        kind: Kind::Official,
+        penalty: false,
+        flags: Z,
+    };
+    pub const TRB_ZP: OpCode = OpCode {
+        op: 0x14,
+        name: "TRB",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: Z,
+    };
+    pub const TSB_ABS: OpCode = OpCode {
+        op: 0x0C,
+        name: "TSB",
+        n: 3,
+        cyc: 6,
+        mode: Mode::Abs,
+        kind: Kind::Official,
+        penalty: false,
+        flags: Z,
+    };
+    pub const TSB_ZP: OpCode = OpCode {
+        op: 0x04,
+        name: "TSB",
+        n: 2,
+        cyc: 5,
+        mode: Mode::Zp,
+        kind: Kind::Official,
+        penalty: false,
+        flags: Z,
+    };
+
+    // Reserved NOPs
+    pub const NOP_02_IMM: OpCode = OpCode {
+        op: 0x02,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_03: OpCode = OpCode {
+        op: 0x03,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_0B: OpCode = OpCode {
+        op: 0x0B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_13: OpCode = OpCode {
+        op: 0x13,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_1B: OpCode = OpCode {
+        op: 0x1B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_22_IMM: OpCode = OpCode {
+        op: 0x22,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_23: OpCode = OpCode {
+        op: 0x23,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_2B: OpCode = OpCode {
+        op: 0x2B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_33: OpCode = OpCode {
+        op: 0x33,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_3B: OpCode = OpCode {
+        op: 0x3B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_42_IMM: OpCode = OpCode {
+        op: 0x42,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_43: OpCode = OpCode {
+        op: 0x43,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_44_ZP: OpCode = OpCode {
+        op: 0x44,
+        name: "NOP",
+        n: 2,
+        cyc: 3,
+        mode: Mode::Zp,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_4B: OpCode = OpCode {
+        op: 0x4B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_53: OpCode = OpCode {
+        op: 0x53,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_54_ZP_X: OpCode = OpCode {
+        op: 0x54,
+        name: "NOP",
+        n: 2,
+        cyc: 4,
+        mode: Mode::ZpX,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_5B: OpCode = OpCode {
+        op: 0x5B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_5C_ABS: OpCode = OpCode {
+        op: 0x5C,
+        name: "NOP",
+        n: 3,
+        cyc: 8,
+        mode: Mode::Abs,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_62_IMM: OpCode = OpCode {
+        op: 0x62,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_63: OpCode = OpCode {
+        op: 0x63,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_6B: OpCode = OpCode {
+        op: 0x6B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_73: OpCode = OpCode {
+        op: 0x73,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_7B: OpCode = OpCode {
+        op: 0x7B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_82_IMM: OpCode = OpCode {
+        op: 0x82,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_83: OpCode = OpCode {
+        op: 0x83,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_8B: OpCode = OpCode {
+        op: 0x8B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_93: OpCode = OpCode {
+        op: 0x93,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_9B: OpCode = OpCode {
+        op: 0x9B,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_A3: OpCode = OpCode {
+        op: 0xA3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_AB: OpCode = OpCode {
+        op: 0xAB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_B3: OpCode = OpCode {
+        op: 0xB3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_BB: OpCode = OpCode {
+        op: 0xBB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_C2_IMM: OpCode = OpCode {
+        op: 0xC2,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_C3: OpCode = OpCode {
+        op: 0xC3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_CB: OpCode = OpCode {
+        op: 0xCB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_D3: OpCode = OpCode {
+        op: 0xD3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_D4_ZP_X: OpCode = OpCode {
+        op: 0xD4,
+        name: "NOP",
+        n: 2,
+        cyc: 4,
+        mode: Mode::ZpX,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_DB: OpCode = OpCode {
+        op: 0xDB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_DC_ABS: OpCode = OpCode {
+        op: 0xDC,
+        name: "NOP",
+        n: 3,
+        cyc: 4,
+        mode: Mode::Abs,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_E2_IMM: OpCode = OpCode {
+        op: 0xE2,
+        name: "NOP",
+        n: 2,
+        cyc: 2,
+        mode: Mode::Immd,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_E3: OpCode = OpCode {
+        op: 0xE3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_EB: OpCode = OpCode {
+        op: 0xEB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_F3: OpCode = OpCode {
+        op: 0xF3,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_F4_ZP_X: OpCode = OpCode {
+        op: 0xF4,
+        name: "NOP",
+        n: 2,
+        cyc: 4,
+        mode: Mode::ZpX,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_FB: OpCode = OpCode {
+        op: 0xFB,
+        name: "NOP",
+        n: 1,
+        cyc: 1,
+        mode: Mode::Impl,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+    pub const NOP_FC_ABS: OpCode = OpCode {
+        op: 0xFC,
+        name: "NOP",
+        n: 3,
+        cyc: 4,
+        mode: Mode::Abs,
+        kind: Kind::Unofficial,
+        penalty: false,
+        flags: NO_FLAGS,
+    };
+
+    // Decode table
+    /// All 65C02 opcode constants, checked like [`super::OPCODES`]
+    const ALL: [OpCode; 256] = [
+        ADC_ABS,
+        ADC_ABS_X,
+        ADC_ABS_Y,
+        ADC_IMM,
+        ADC_IND_Y,
+        ADC_X_IND,
+        ADC_ZP,
+        ADC_ZP_X,
+        AND_ABS,
+        AND_ABS_X,
+        AND_ABS_Y,
+        AND_IMM,
+        AND_IND_Y,
+        AND_X_IND,
+        AND_ZP,
+        AND_ZP_X,
+        ASL_A,
+        ASL_ABS,
+        ASL_ABS_X,
+        ASL_ZP,
+        ASL_ZP_X,
+        BCC_REL,
+        BCS_REL,
+        BEQ_REL,
+        BIT_ABS,
+        BIT_ZP,
+        BMI_REL,
+        BNE_REL,
+        BPL_REL,
+        BRK,
+        BVC_REL,
+        BVS_REL,
+        CLC,
+        CLD,
+        CLI,
+        CLV,
+        CMP_ABS,
+        CMP_ABS_X,
+        CMP_ABS_Y,
+        CMP_IMM,
+        CMP_IND_Y,
+        CMP_X_IND,
+        CMP_ZP,
+        CMP_ZP_X,
+        CPX_ABS,
+        CPX_IMM,
+        CPX_ZP,
+        CPY_ABS,
+        CPY_IMM,
+        CPY_ZP,
+        DEC_ABS,
+        DEC_ABS_X,
+        DEC_ZP,
+        DEC_ZP_X,
+        DEX,
+        DEY,
+        EOR_ABS,
+        EOR_ABS_X,
+        EOR_ABS_Y,
+        EOR_IMM,
+        EOR_IND_Y,
+        EOR_X_IND,
+        EOR_ZP,
+        EOR_ZP_X,
+        INC_ABS,
+        INC_ABS_X,
+        INC_ZP,
+        INC_ZP_X,
+        INX,
+        INY,
+        JMP_ABS,
+        JMP_IND,
+        JSR_ABS,
+        LDA_ABS,
+        LDA_ABS_X,
+        LDA_ABS_Y,
+        LDA_IMM,
+        LDA_IND_Y,
+        LDA_X_IND,
+        LDA_ZP,
+        LDA_ZP_X,
+        LDX_ABS,
+        LDX_ABS_Y,
+        LDX_IMM,
+        LDX_ZP,
+        LDX_ZP_Y,
+        LDY_ABS,
+        LDY_ABS_X,
+        LDY_IMM,
+        LDY_ZP,
+        LDY_ZP_X,
+        LSR_A,
+        LSR_ABS,
+        LSR_ABS_X,
+        LSR_ZP,
+        LSR_ZP_X,
+        NOP,
+        ORA_ABS,
+        ORA_ABS_X,
+        ORA_ABS_Y,
+        ORA_IMM,
+        ORA_IND_Y,
+        ORA_X_IND,
+        ORA_ZP,
+        ORA_ZP_X,
+        PHA,
+        PHP,
+        PLA,
+        PLP,
+        ROL_A,
+        ROL_ABS,
+        ROL_ABS_X,
+        ROL_ZP,
+        ROL_ZP_X,
+        ROR_A,
+        ROR_ABS,
+        ROR_ABS_X,
+        ROR_ZP,
+        ROR_ZP_X,
+        RTI,
+        RTS,
+        SBC_ABS,
+        SBC_ABS_X,
+        SBC_ABS_Y,
+        SBC_IMM,
+        SBC_IND_Y,
+        SBC_X_IND,
+        SBC_ZP,
+        SBC_ZP_X,
+        SEC,
+        SED,
+        SEI,
+        STA_ABS,
+        STA_ABS_X,
+        STA_ABS_Y,
+        STA_IND_Y,
+        STA_X_IND,
+        STA_ZP,
+        STA_ZP_X,
+        STX_ABS,
+        STX_ZP,
+        STX_ZP_Y,
+        STY_ABS,
+        STY_ZP,
+        STY_ZP_X,
+        TAX,
+        TAY,
+        TSX,
+        TXA,
+        TXS,
+        TYA,
+        ADC_ZP_IND,
+        AND_ZP_IND,
+        BBR0_ZP_REL,
+        BBR1_ZP_REL,
+        BBR2_ZP_REL,
+        BBR3_ZP_REL,
+        BBR4_ZP_REL,
+        BBR5_ZP_REL,
+        BBR6_ZP_REL,
+        BBR7_ZP_REL,
+        BBS0_ZP_REL,
+        BBS1_ZP_REL,
+        BBS2_ZP_REL,
+        BBS3_ZP_REL,
+        BBS4_ZP_REL,
+        BBS5_ZP_REL,
+        BBS6_ZP_REL,
+        BBS7_ZP_REL,
+        BIT_ABS_X,
+        BIT_IMM,
+        BIT_ZP_X,
+        BRA_REL,
+        CMP_ZP_IND,
+        DEC_A,
+        EOR_ZP_IND,
+        INC_A,
+        JMP_ABS_X_IND,
+        LDA_ZP_IND,
+        ORA_ZP_IND,
+        PHX,
+        PHY,
+        PLX,
+        PLY,
+        RMB0_ZP,
+        RMB1_ZP,
+        RMB2_ZP,
+        RMB3_ZP,
+        RMB4_ZP,
+        RMB5_ZP,
+        RMB6_ZP,
+        RMB7_ZP,
+        SBC_ZP_IND,
+        SMB0_ZP,
+        SMB1_ZP,
+        SMB2_ZP,
+        SMB3_ZP,
+        SMB4_ZP,
+        SMB5_ZP,
+        SMB6_ZP,
+        SMB7_ZP,
+        STA_ZP_IND,
+        STZ_ABS,
+        STZ_ABS_X,
+        STZ_ZP,
+        STZ_ZP_X,
+        TRB_ABS,
+        TRB_ZP,
+        TSB_ABS,
+        TSB_ZP,
+        NOP_02_IMM,
+        NOP_03,
+        NOP_0B,
+        NOP_13,
+        NOP_1B,
+        NOP_22_IMM,
+        NOP_23,
+        NOP_2B,
+        NOP_33,
+        NOP_3B,
+        NOP_42_IMM,
+        NOP_43,
+        NOP_44_ZP,
+        NOP_4B,
+        NOP_53,
+        NOP_54_ZP_X,
+        NOP_5B,
+        NOP_5C_ABS,
+        NOP_62_IMM,
+        NOP_63,
+        NOP_6B,
+        NOP_73,
+        NOP_7B,
+        NOP_82_IMM,
+        NOP_83,
+        NOP_8B,
+        NOP_93,
+        NOP_9B,
+        NOP_A3,
+        NOP_AB,
+        NOP_B3,
+        NOP_BB,
+        NOP_C2_IMM,
+        NOP_C3,
+        NOP_CB,
+        NOP_D3,
+        NOP_D4_ZP_X,
+        NOP_DB,
+        NOP_DC_ABS,
+        NOP_E2_IMM,
+        NOP_E3,
+        NOP_EB,
+        NOP_F3,
+        NOP_F4_ZP_X,
+        NOP_FB,
+        NOP_FC_ABS,
+    ];
+
+    /// 65C02 OpCode lookup table indexed by opcode byte
+    pub static OPCODES: [OpCode; 256] = decode_table(&ALL);
+
+    /// decode a 65C02 opcode byte
+    pub fn decode(op: u8) -> &'static OpCode {
+        &OPCODES[op as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_by_byte() {
+        for (i, code) in OPCODES.iter().enumerate() {
+            assert_eq!(code.op as usize, i, "{} at ${:02X}", code.name, i);
+        }
+        let codes = [
+            (0x00, "BRK", 1, 7),
+            (0x40, "RTI", 1, 6),
+            (0x6C, "JMP", 3, 5),
+            (0xA9, "LDA", 2, 2),
+            (0xDF, "DCP", 3, 7),
+            (0xEA, "NOP", 1, 2),
+            (0xF2, "KIL", 1, 2),
+        ];
+        for (op, name, n, cyc) in codes {
+            let code = decode(op);
+            assert_eq!((code.name, code.n, code.cyc), (name, n, cyc));
+        }
+    }
+}
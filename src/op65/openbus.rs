@@ -0,0 +1,191 @@
+//! NES open bus
+//!
+//! Reading an address that no device drives on the NES returns the value
+//! left on the data bus by the previous access. [`OpenBus`] wraps a
+//! [`BusMut`], remembers the last value transferred and serves it for
+//! unmapped reads instead of failing with [`AddressError::Unavailable`]. See
+//! <https://www.nesdev.org/wiki/Open_bus_behavior>.
+
+use crate::op65::context::{AddressError, BusMut};
+
+/// Unmapped selects how unmapped accesses of a region are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unmapped {
+    /// reads return the last data-bus value and writes are ignored
+    OpenBus,
+    /// the error of the wrapped bus is returned
+    Error,
+}
+
+/// OpenBus serves the last data-bus value for unmapped reads of a bus
+pub struct OpenBus<B: BusMut> {
+    /// wrapped bus
+    inner: B,
+    /// last value on the data bus
+    latch: u8,
+    /// per-region handling, the last matching region wins
+    regions: Vec<(u16, u16, Unmapped)>,
+    /// handling outside every region
+    default: Unmapped,
+}
+
+impl<B: BusMut> OpenBus<B> {
+    /// wrap a bus, with open-bus behavior on the whole address space
+    pub fn new(inner: B) -> OpenBus<B> {
+        OpenBus {
+            inner,
+            latch: 0,
+            regions: Vec::new(),
+            default: Unmapped::OpenBus,
+        }
+    }
+
+    /// select the handling of unmapped accesses in `start..=end`
+    pub fn with_region(mut self, start: u16, end: u16, mode: Unmapped) -> Self {
+        self.regions.push((start, end, mode));
+        self
+    }
+
+    /// select the handling of unmapped accesses outside every region
+    pub fn with_default(mut self, mode: Unmapped) -> Self {
+        self.default = mode;
+        self
+    }
+
+    /// last value on the data bus
+    pub fn last(&self) -> u8 {
+        self.latch
+    }
+
+    /// wrapped bus
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// wrapped bus, for mutation
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// unwrap the bus
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// handling of unmapped accesses at an address
+    fn mode(&self, addr: u16) -> Unmapped {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(start, end, _)| (*start..=*end).contains(&addr))
+            .map_or(self.default, |(_, _, mode)| *mode)
+    }
+}
+
+// BusMut for OpenBus
+impl<B: BusMut> BusMut for OpenBus<B> {
+    /// read a byte, a read of an unmapped or write-only address returns the
+    /// last data-bus value
+    fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+        match self.inner.get(addr) {
+            Ok(val) => {
+                self.latch = val;
+                Ok(val)
+            }
+            Err(AddressError::Unavailable(_) | AddressError::WriteOnly(_))
+                if self.mode(addr) == Unmapped::OpenBus =>
+            {
+                Ok(self.latch)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// write a byte, the value stays on the data bus even when nothing is
+    /// mapped at the address
+    fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+        self.latch = val;
+        match self.inner.set(addr, val) {
+            Err(AddressError::Unavailable(_))
+                if self.mode(addr) == Unmapped::OpenBus =>
+            {
+                Ok(())
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RAM at $0000-$00FF and a write-only register at $4000
+    struct Regs(Vec<u8>);
+
+    impl BusMut for Regs {
+        fn get(&mut self, addr: u16) -> Result<u8, AddressError> {
+            match addr {
+                0x0000..=0x00FF => Ok(self.0[addr as usize]),
+                0x4000 => Err(AddressError::WriteOnly(addr)),
+                _ => Err(AddressError::Unavailable(addr)),
+            }
+        }
+
+        fn set(&mut self, addr: u16, val: u8) -> Result<(), AddressError> {
+            match addr {
+                0x0000..=0x00FF => self.0[addr as usize] = val,
+                0x4000 => (),
+                _ => return Err(AddressError::Unavailable(addr)),
+            }
+            Ok(())
+        }
+    }
+
+    fn regs() -> Regs {
+        let mut ram = vec![0; 0x100];
+        ram[0x10] = 0x5A;
+        Regs(ram)
+    }
+
+    #[test]
+    fn latch() {
+        let mut bus = OpenBus::new(regs());
+        assert_eq!(bus.get(0x0010).ok(), Some(0x5A));
+        assert_eq!(bus.last(), 0x5A);
+        assert_eq!(bus.get(0x6000).ok(), Some(0x5A));
+        // writes drive the data bus, mapped or not
+        bus.set(0x4000, 0x33).ok();
+        assert_eq!(bus.get(0x6000).ok(), Some(0x33));
+        assert!(bus.set(0x7000, 0x77).is_ok());
+        assert_eq!(bus.last(), 0x77);
+        // a read of the write-only register returns the latch as well
+        assert_eq!(bus.get(0x4000).ok(), Some(0x77));
+        assert_eq!(bus.into_inner().0[0x10], 0x5A);
+    }
+
+    #[test]
+    fn regions() {
+        let mut bus = OpenBus::new(regs())
+            .with_default(Unmapped::Error)
+            .with_region(0x4000, 0x5FFF, Unmapped::OpenBus)
+            .with_region(0x5000, 0x50FF, Unmapped::Error);
+        bus.get(0x0010).ok();
+        assert!(matches!(bus.get(0x6000), Err(AddressError::Unavailable(_))));
+        assert!(matches!(
+            bus.set(0x6000, 0),
+            Err(AddressError::Unavailable(_))
+        ));
+        // the failed write still drove the data bus
+        assert_eq!(bus.get(0x4000).ok(), Some(0x00));
+        assert!(bus.set(0x4100, 0x5A).is_ok());
+        assert_eq!(bus.get(0x4100).ok(), Some(0x5A));
+        // the last matching region wins
+        assert!(matches!(bus.get(0x5010), Err(AddressError::Unavailable(_))));
+        assert_eq!(bus.get(0x5100).ok(), Some(0x5A));
+
+        // errors of the inner bus pass through unless open bus is selected
+        let mut bus = OpenBus::new(regs()).with_default(Unmapped::Error);
+        assert!(matches!(bus.get(0x4000), Err(AddressError::WriteOnly(_))));
+    }
+}